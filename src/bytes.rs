@@ -0,0 +1,132 @@
+use schemars::schema::RootSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    serialize,
+    tree::{difference, Ordered},
+    Error,
+};
+
+/// Serialize an item to bytes and parse them back, reporting what
+/// `serde_json::to_value` would have hidden: duplicate keys, and any
+/// disagreement between the two ways of serializing. The errors also include
+/// those from [`serialize::to_checked_value`]. Along with the parsed value,
+/// this returns it with properties in the order in which they were written.
+pub fn serialize<T: Serialize>(
+    item: &T,
+    schema: &RootSchema,
+    round_trip: bool,
+) -> Result<(Value, Ordered, Vec<Error>), Error> {
+    let (other, mut errors) = serialize::to_checked_value(item, schema, round_trip)?;
+    let bytes = serialize::to_vec(item, schema)?;
+
+    let (written, duplicates) = Ordered::parse_strict(&bytes).map_err(Error::InvalidOutput)?;
+    let value = written.to_value();
+
+    errors.extend(
+        duplicates
+            .into_iter()
+            .map(|(pointer, key)| Error::DuplicateKey { pointer, key }),
+    );
+    if let Some((pointer, details)) =
+        difference(String::new(), ("to_value", &other), ("to_vec", &value))
+    {
+        errors.push(Error::ValueMismatch { pointer, details });
+    }
+
+    Ok((value, written, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, collections::BTreeMap};
+
+    use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+    use serde::{ser::SerializeMap, Serialize, Serializer};
+    use serde_json::{json, Value};
+
+    use crate::{validate, validate_with_options, Error, Options, PropertyOrder};
+
+    fn options() -> Options {
+        Options {
+            check_bytes: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_flatten_collision() {
+        #[derive(Serialize, JsonSchema)]
+        struct Record {
+            id: u32,
+            #[serde(flatten)]
+            extra: BTreeMap<String, Value>,
+        }
+
+        let item = Record {
+            id: 1,
+            extra: [("id".to_string(), json!(2))].into_iter().collect(),
+        };
+
+        // The collision is invisible to `to_value`.
+        validate(&item).unwrap();
+
+        let errors = validate_with_options(&item, &options()).errors;
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["the serialized object at the root has the key id more than once"],
+        );
+    }
+
+    struct Flaky(Cell<u32>);
+
+    impl Serialize for Flaky {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let n = self.0.get();
+            self.0.set(n + 1);
+            let mut map = serializer.serialize_map(None)?;
+            map.serialize_entry("n", &n)?;
+            map.end()
+        }
+    }
+
+    impl JsonSchema for Flaky {
+        fn schema_name() -> String {
+            "Flaky".to_string()
+        }
+
+        fn json_schema(_: &mut SchemaGenerator) -> Schema {
+            serde_json::from_value(json!({ "type": "object" })).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let errors = validate_with_options(&Flaky(Cell::new(1)), &options()).errors;
+        match errors.as_slice() {
+            [Error::ValueMismatch { pointer, details }] => {
+                assert_eq!(pointer, "/n");
+                // Which serializer sees which count is up to the order in
+                // which they run.
+                assert!(details.contains("to_value"), "{}", details);
+                assert!(details.contains("to_vec"), "{}", details);
+            }
+            other => panic!("unexpected errors {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_property_order_from_bytes() {
+        // The bytes parsed to check them also give the order of properties,
+        // so the item is serialized once for each of `to_value` and
+        // `to_vec`.
+        let item = Flaky(Cell::new(1));
+        let options = Options {
+            property_order: Some(PropertyOrder::Alphabetical),
+            ..options()
+        };
+        validate_with_options(&item, &options);
+        assert_eq!(item.0.get(), 3);
+    }
+}
@@ -0,0 +1,221 @@
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+use schemars::schema::{Schema, SchemaObject};
+use serde_json::Value;
+
+use crate::{
+    schema::{validate_schema, Context},
+    Error, ErrorKind, Warning,
+};
+
+const PADDING_INDIFFERENT: GeneralPurposeConfig =
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+const STANDARD: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, PADDING_INDIFFERENT);
+const URL_SAFE: GeneralPurpose = GeneralPurpose::new(&alphabet::URL_SAFE, PADDING_INDIFFERENT);
+
+/// Decode a string according to its `contentEncoding` and check it against
+/// its `contentMediaType` and `contentSchema`.
+pub fn validate_content(
+    path: &str,
+    schema: &SchemaObject,
+    cx: &Context,
+    value: &Value,
+) -> Result<(), Error> {
+    // The content keywords only apply to strings.
+    let Some(s) = value.as_str() else {
+        return Ok(());
+    };
+
+    let extensions = &schema.extensions;
+    let encoding = extensions.get("contentEncoding");
+    let media_type = extensions.get("contentMediaType");
+    let content_schema = extensions.get("contentSchema");
+
+    if encoding.is_none() && media_type.is_none() {
+        return Ok(());
+    }
+
+    let bytes = match encoding {
+        None => s.as_bytes().to_vec(),
+        Some(Value::String(encoding)) => match encoding.to_ascii_lowercase().as_str() {
+            "base64" => STANDARD
+                .decode(s)
+                .or_else(|_| URL_SAFE.decode(s))
+                .map_err(|e| {
+                    cx.invalid_value(
+                        ErrorKind::ContentEncoding,
+                        format!("{}.contentEncoding", path),
+                        Schema::Object(schema.clone()),
+                        value,
+                        format!("the string is not valid base64: {}", e),
+                    )
+                })?,
+            "7bit" | "8bit" | "binary" => s.as_bytes().to_vec(),
+            _ => {
+                cx.warn(Warning::UnsupportedEncoding {
+                    path: path.to_string(),
+                    encoding: encoding.clone(),
+                });
+                return Ok(());
+            }
+        },
+        Some(_) => {
+            return Err(Error::InvalidSchema {
+                path: path.to_string(),
+                details: "`contentEncoding` must be a string".to_string(),
+            })
+        }
+    };
+
+    let media_type = match media_type {
+        None => return Ok(()),
+        Some(Value::String(media_type)) => media_type,
+        Some(_) => {
+            return Err(Error::InvalidSchema {
+                path: path.to_string(),
+                details: "`contentMediaType` must be a string".to_string(),
+            })
+        }
+    };
+
+    if !is_json_media_type(media_type) {
+        return Ok(());
+    }
+
+    let content = serde_json::from_slice::<Value>(&bytes).map_err(|e| {
+        cx.invalid_value(
+            ErrorKind::ContentMediaType,
+            format!("{}.contentMediaType", path),
+            Schema::Object(schema.clone()),
+            value,
+            format!("the content is not valid {}: {}", media_type, e),
+        )
+    })?;
+
+    if let Some(content_schema) = content_schema {
+        let content_schema =
+            serde_json::from_value::<Schema>(content_schema.clone()).map_err(|_| {
+                Error::InvalidSchema {
+                    path: path.to_string(),
+                    details: "`contentSchema` must be a schema".to_string(),
+                }
+            })?;
+        validate_schema(
+            &format!("{}.contentSchema", path),
+            &content_schema,
+            cx,
+            &content,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Matches `application/json` as well as structured syntax suffixes such as
+/// `application/problem+json`; parameters such as `charset` are ignored.
+fn is_json_media_type(media_type: &str) -> bool {
+    let essence = media_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    essence == "application/json" || essence.ends_with("+json")
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::{
+        gen::SchemaGenerator,
+        schema::{InstanceType, Schema, SchemaObject},
+        JsonSchema,
+    };
+    use serde::Serialize;
+    use serde_json::json;
+
+    use crate::{validate, validate_with_options, Options};
+
+    #[derive(Serialize)]
+    struct Blob(String);
+
+    impl JsonSchema for Blob {
+        fn schema_name() -> String {
+            "Blob".to_string()
+        }
+
+        fn json_schema(_: &mut SchemaGenerator) -> Schema {
+            let mut schema = SchemaObject {
+                instance_type: Some(InstanceType::String.into()),
+                ..Default::default()
+            };
+            schema
+                .extensions
+                .insert("contentEncoding".to_string(), json!("base64"));
+            schema
+                .extensions
+                .insert("contentMediaType".to_string(), json!("application/json"));
+            schema.extensions.insert(
+                "contentSchema".to_string(),
+                json!({ "type": "object", "required": ["id"] }),
+            );
+            schema.into()
+        }
+    }
+
+    fn options() -> Options {
+        Options {
+            decode_content: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_content() {
+        // {"id":1}
+        validate_with_options(&Blob("eyJpZCI6MX0=".to_string()), &options())
+            .into_result()
+            .unwrap();
+        validate_with_options(&Blob("eyJpZCI6MX0".to_string()), &options())
+            .into_result()
+            .unwrap();
+
+        // Content is only checked when asked.
+        validate(&Blob("not base64!".to_string())).unwrap();
+
+        let err = validate_with_options(&Blob("not base64!".to_string()), &options())
+            .into_result()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("$.contentEncoding (in Blob): the string is not valid base64"));
+
+        // [1,2,
+        let err = validate_with_options(&Blob("WzEsMiw=".to_string()), &options())
+            .into_result()
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("$.contentMediaType (in Blob): the content is not valid application/json")
+        );
+
+        // {"name":"x"}
+        let err = validate_with_options(&Blob("eyJuYW1lIjoieCJ9".to_string()), &options())
+            .into_result()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("$.contentSchema (in Blob): the property id is required but absent"));
+    }
+
+    #[test]
+    fn test_url_safe() {
+        // {"id":">>>?"} encodes to characters that differ between alphabets.
+        validate_with_options(&Blob("eyJpZCI6Ij4+Pj8ifQ==".to_string()), &options())
+            .into_result()
+            .unwrap();
+        validate_with_options(&Blob("eyJpZCI6Ij4-Pj8ifQ==".to_string()), &options())
+            .into_result()
+            .unwrap();
+    }
+}
@@ -0,0 +1,110 @@
+use schemars::{schema_for, JsonSchema};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{serialize, tree::difference, validate_value, Error, Options};
+
+/// Confirm that an item of one type serializes to a value that conforms to
+/// the schema of another and deserializes into it.
+///
+/// This tests the wire compatibility of two types that describe the same
+/// format, such as a server's type and a client's generated from its
+/// schema. See [`validate`](crate::validate).
+pub fn validate_cross<A: Serialize, B: JsonSchema + DeserializeOwned>(a: &A) -> Result<(), Error> {
+    cross::<A, B>(a).map(|_| ())
+}
+
+/// Like [`validate_cross`], and also check that serializing the
+/// deserialized item reproduces the original value.
+pub fn validate_cross_round_trip<A, B>(a: &A) -> Result<(), Error>
+where
+    A: Serialize,
+    B: JsonSchema + Serialize + DeserializeOwned,
+{
+    let (value, b) = cross::<A, B>(a)?;
+    let round_trip = serde_json::to_value(&b)?;
+    match difference(
+        String::new(),
+        ("the original", &value),
+        ("the round trip", &round_trip),
+    ) {
+        Some((pointer, details)) => Err(Error::CrossMismatch {
+            target: B::schema_name(),
+            pointer,
+            details,
+        }),
+        None => Ok(()),
+    }
+}
+
+fn cross<A: Serialize, B: JsonSchema + DeserializeOwned>(a: &A) -> Result<(Value, B), Error> {
+    let schema = schema_for!(B);
+    let value = serialize::to_value(a, &schema)?;
+    validate_value(&schema, &value, &Options::default()).into_result()?;
+    let b = B::deserialize(&value).map_err(|source| Error::CrossDeserialization {
+        target: B::schema_name(),
+        source,
+    })?;
+    Ok((value, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+
+    use crate::{validate_cross, validate_cross_round_trip};
+
+    #[derive(Serialize)]
+    struct ServerUser {
+        id: u64,
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        email: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    struct ClientUser {
+        id: u64,
+        name: String,
+        email: Option<String>,
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    #[allow(dead_code)]
+    struct LegacyUser {
+        id: u32,
+        name: String,
+    }
+
+    #[test]
+    fn test_cross() {
+        let user = ServerUser {
+            id: 1,
+            name: "ahl".to_string(),
+            email: None,
+        };
+        assert!(validate_cross::<_, ClientUser>(&user).is_ok());
+
+        assert_eq!(
+            validate_cross_round_trip::<_, ClientUser>(&user)
+                .unwrap_err()
+                .to_string(),
+            "serializing the value as ClientUser differs from the original at /email: the \
+            original omits the property",
+        );
+
+        assert!(validate_cross::<_, LegacyUser>(&user).is_ok());
+        let user = ServerUser {
+            id: 5_000_000_000,
+            ..user
+        };
+        assert_eq!(
+            validate_cross::<_, LegacyUser>(&user)
+                .unwrap_err()
+                .to_string(),
+            "the value doesn't deserialize into LegacyUser: invalid value: integer \
+            `5000000000`, expected u32",
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use schemars::{schema_for, JsonSchema};
+use serde::Serialize;
+
+use crate::{
+    serialize,
+    tree::{difference, Ordered},
+    validate, Error,
+};
+
+/// Confirm that an item matches its schema and that serializing it
+/// repeatedly produces the same bytes each time.
+///
+/// Serialization that iterates over a collection with an unspecified order,
+/// such as a `HashSet` built afresh on each call, can write arrays or object
+/// properties in a different order from one run to the next. The item is
+/// serialized `runs` times and each result is compared with the first. See
+/// [`validate`].
+pub fn validate_deterministic<T: JsonSchema + Serialize>(
+    item: &T,
+    runs: usize,
+) -> Result<(), Error> {
+    validate(item)?;
+    let schema = schema_for!(T);
+    compare_runs(runs, || serialize::to_vec(item, &schema))
+}
+
+/// Like [`validate_deterministic`], but serialize a fresh clone of the item
+/// for each run, which catches orders that depend on how the item was
+/// constructed rather than on the item itself.
+pub fn validate_deterministic_clones<T: JsonSchema + Serialize + Clone>(
+    item: &T,
+    runs: usize,
+) -> Result<(), Error> {
+    validate(item)?;
+    let schema = schema_for!(T);
+    compare_runs(runs, || serialize::to_vec(&item.clone(), &schema))
+}
+
+fn compare_runs(
+    runs: usize,
+    mut serialize: impl FnMut() -> Result<Vec<u8>, Error>,
+) -> Result<(), Error> {
+    let first = serialize()?;
+    for run in 1..runs {
+        let bytes = serialize()?;
+        if bytes == first {
+            continue;
+        }
+        let a = Ordered::parse(&first).map_err(Error::InvalidOutput)?;
+        let b = Ordered::parse(&bytes).map_err(Error::InvalidOutput)?;
+        let (pointer, details) = difference(
+            String::new(),
+            ("the first run", &a),
+            (&format!("run {}", run + 1), &b),
+        )
+        .unwrap_or_else(|| {
+            (
+                String::new(),
+                "the output differs only in whitespace or number formatting".to_string(),
+            )
+        });
+        return Err(Error::Nondeterministic {
+            run: run + 1,
+            pointer,
+            details,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use schemars::JsonSchema;
+    use serde::{ser::SerializeMap, Serialize, Serializer};
+
+    use super::compare_runs;
+    use crate::{validate_deterministic, Error};
+
+    /// Alternates the order of its properties, as serializing a freshly
+    /// built `HashMap` might.
+    #[derive(JsonSchema)]
+    struct Point {
+        x: u32,
+        y: u32,
+        #[schemars(skip)]
+        calls: Cell<usize>,
+    }
+
+    impl Serialize for Point {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let calls = self.calls.get();
+            self.calls.set(calls + 1);
+            let mut map = serializer.serialize_map(Some(2))?;
+            if calls.is_multiple_of(2) {
+                map.serialize_entry("x", &self.x)?;
+                map.serialize_entry("y", &self.y)?;
+            } else {
+                map.serialize_entry("y", &self.y)?;
+                map.serialize_entry("x", &self.x)?;
+            }
+            map.end()
+        }
+    }
+
+    #[derive(Serialize, JsonSchema)]
+    struct Path {
+        points: Vec<Point>,
+    }
+
+    #[test]
+    fn test_deterministic() {
+        assert!(validate_deterministic(&vec![1, 2, 3], 5).is_ok());
+
+        let item = Path {
+            points: vec![Point {
+                x: 1,
+                y: 2,
+                calls: Cell::new(0),
+            }],
+        };
+        // Whichever order the first run writes, the second writes the other.
+        match validate_deterministic(&item, 5) {
+            Err(Error::Nondeterministic { run, pointer, .. }) => {
+                assert_eq!(run, 2);
+                assert_eq!(pointer, "/points/0");
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_compare_runs() {
+        let mut outputs = [r#"{"x":1,"y":2}"#, r#"{"x":1,"y":2}"#, r#"{"y":2,"x":1}"#].into_iter();
+        match compare_runs(3, || Ok(outputs.next().unwrap().as_bytes().to_vec())) {
+            Err(Error::Nondeterministic {
+                run,
+                pointer,
+                details,
+            }) => {
+                assert_eq!(run, 3);
+                assert_eq!(pointer, "");
+                assert_eq!(
+                    details,
+                    "the first run writes the properties in the order x, y but run 3 in the \
+                    order y, x",
+                );
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+
+use miette::{Diagnostic, LabeledSpan, SourceCode};
+
+use crate::{report::source_spans, Error, ValidationOutcome};
+
+fn code(error: &Error) -> String {
+    let code = match error {
+        Error::SerializationError { .. } => "serialization",
+        Error::InvalidSchema { .. } => "invalid_schema",
+        Error::InvalidValue { kind, .. } => kind.code(),
+        Error::PromotedWarning(_) => "promoted_warning",
+        Error::InvalidOutput(_) => "invalid_output",
+        Error::DuplicateKey { .. } => "duplicate_key",
+        Error::ValueMismatch { .. } => "value_mismatch",
+        Error::NonFiniteFloat { .. } => "non_finite_float",
+        Error::IntegerOutOfRange { .. } => "integer_out_of_range",
+        Error::FloatRoundTrip { .. } => "float_round_trip",
+        Error::Nondeterministic { .. } => "nondeterministic",
+        Error::InvalidExample { .. } => "invalid_example",
+        Error::UndeserializableExample { .. } => "undeserializable_example",
+        Error::MissingDefinition { .. } => "missing_definition",
+        Error::InvalidDefault { .. } => "invalid_default",
+        Error::DefaultMismatch { .. } => "default_mismatch",
+        Error::MutantDisagreement { .. } => "mutant_disagreement",
+        Error::UndeserializableVariant { .. } => "undeserializable_variant",
+        Error::VariantRoundTrip { .. } => "variant_round_trip",
+        Error::UnlistedVariant { .. } => "unlisted_variant",
+        Error::NothingEnumerated { .. } => "nothing_enumerated",
+        Error::NotRejected { .. } => "not_rejected",
+        Error::CrossDeserialization { .. } => "cross_deserialization",
+        Error::CrossMismatch { .. } => "cross_mismatch",
+        Error::SchemasDiffer { .. } => "schemas_differ",
+    };
+    format!("does_it_json::{}", code)
+}
+
+impl Diagnostic for Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(code(self)))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        match self {
+            Error::InvalidValue { hints, .. } if !hints.is_empty() => {
+                Some(Box::new(hints.join("\n")))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The errors from validating an item, as a single diagnostic whose source is
+/// the pretty-printed item, with a label at each offending node. Each error
+/// is also a related diagnostic, with its own code and help.
+#[derive(Debug)]
+pub struct Failure {
+    source: String,
+    labels: Vec<LabeledSpan>,
+    errors: Vec<Error>,
+}
+
+impl ValidationOutcome {
+    /// A diagnostic describing the errors, if there were any.
+    pub fn into_diagnostic(self) -> Option<Failure> {
+        if self.errors.is_empty() {
+            return None;
+        }
+
+        let pointers = self
+            .errors
+            .iter()
+            .map(|error| match error {
+                Error::InvalidValue { pointer, .. } => pointer.as_str(),
+                _ => "",
+            })
+            .collect::<Vec<_>>();
+        let (source, ranges) = source_spans(&self.value, &pointers);
+
+        let labels = self
+            .errors
+            .iter()
+            .zip(ranges)
+            .filter_map(|(error, range)| match error {
+                Error::InvalidValue { details, .. } => {
+                    Some(LabeledSpan::at(range?, details.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        Some(Failure {
+            source,
+            labels,
+            errors: self.errors,
+        })
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.errors.as_slice() {
+            [error] => write!(f, "{}", error),
+            errors => write!(f, "{} errors validating the value", errors.len()),
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+impl Diagnostic for Failure {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        match self.errors.as_slice() {
+            [error] => Some(Box::new(code(error))),
+            _ => None,
+        }
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        match self.errors.as_slice() {
+            [error] => error.help(),
+            _ => None,
+        }
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(self.labels.iter().cloned()))
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        match self.errors.as_slice() {
+            [_] => None,
+            errors => Some(Box::new(
+                errors.iter().map(|error| error as &dyn Diagnostic),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::Diagnostic;
+    use schemars::JsonSchema;
+    use serde::Serialize;
+
+    use crate::{validate, validate_with_options, Options};
+
+    #[derive(Serialize, JsonSchema)]
+    struct Gauge {
+        #[schemars(rename = "reading")]
+        value: u32,
+        unit: Unit,
+    }
+
+    #[derive(Serialize, JsonSchema)]
+    #[allow(dead_code)]
+    enum Unit {
+        #[schemars(rename = "celsius")]
+        Celsius,
+    }
+
+    #[test]
+    fn test_error() {
+        let err = validate(&Unit::Celsius).unwrap_err();
+        assert_eq!(err.code().unwrap().to_string(), "does_it_json::enum",);
+        assert_eq!(
+            err.help().unwrap().to_string(),
+            r#""celsius" differs only in case or word separators; do serde and schemars agree on `rename_all`?"#,
+        );
+    }
+
+    #[test]
+    fn test_failure() {
+        let item = Gauge {
+            value: 3,
+            unit: Unit::Celsius,
+        };
+        let failure = validate_with_options(&item, &Options::default())
+            .into_diagnostic()
+            .unwrap();
+
+        let source = failure.source_code().unwrap();
+        let labels = failure
+            .labels()
+            .unwrap()
+            .map(|label| {
+                let contents = source.read_span(label.inner(), 0, 0).unwrap();
+                (
+                    String::from_utf8(contents.data().to_vec()).unwrap(),
+                    label.label().unwrap().to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            vec![
+                (
+                    "{\n  \"unit\": \"Celsius\",\n  \"value\": 3\n}".to_string(),
+                    "the property reading is required but absent".to_string(),
+                ),
+                (
+                    "\"Celsius\"".to_string(),
+                    "not a valid enumerated value".to_string(),
+                ),
+            ],
+        );
+
+        let codes = failure
+            .related()
+            .unwrap()
+            .map(|related| related.code().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            codes,
+            vec!["does_it_json::missing_required", "does_it_json::enum"],
+        );
+    }
+}
@@ -0,0 +1,331 @@
+use std::fmt;
+
+use schemars::schema::{Schema, SchemaObject};
+use serde_json::Value;
+
+use crate::schema::{resolve_reference, Context};
+
+/// The ways serde can represent an enum.
+#[derive(Debug, PartialEq)]
+enum Representation {
+    External,
+    Internal { tag: String },
+    Adjacent { tag: String, content: String },
+    Untagged,
+}
+
+impl fmt::Display for Representation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Representation::External => f.write_str("externally tagged"),
+            Representation::Internal { tag } => write!(f, "internal tag `{}`", tag),
+            Representation::Adjacent { tag, content } => {
+                write!(f, "adjacent tag `{}` and content `{}`", tag, content)
+            }
+            Representation::Untagged => f.write_str("untagged"),
+        }
+    }
+}
+
+/// Explain why a value matched none of the variants of an enum's schema by
+/// comparing the serde representation the value uses with the one the schema
+/// describes.
+pub fn diagnose(branches: &[Schema], cx: &Context, value: &Value) -> Option<String> {
+    let branches = branches
+        .iter()
+        .map(|branch| match branch {
+            Schema::Object(SchemaObject {
+                reference: Some(reference),
+                ..
+            }) => resolve_reference("", reference, cx).ok(),
+            _ => Some(branch),
+        })
+        .map(|branch| match branch? {
+            Schema::Object(obj) => Some(obj),
+            Schema::Bool(_) => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let (expected, variants) = schema_representation(&branches)?;
+    // Without tags there's no representation to compare against.
+    if expected == Representation::Untagged {
+        return None;
+    }
+    let (actual, variant) = value_representation(value, &expected, &variants);
+
+    if actual == expected {
+        let variant = variant?;
+        if variants.contains(&variant) {
+            return None;
+        }
+        return Some(format!(
+            "value has the variant {}, which is not among the schema's variants {}",
+            variant,
+            variants.join(", "),
+        ));
+    }
+
+    let excerpt = match (&actual, variant) {
+        (_, None) | (Representation::Untagged, _) => String::new(),
+        (Representation::External, Some(variant)) if value.is_string() => {
+            format!(" (`{:?}`)", variant)
+        }
+        (Representation::External, Some(variant)) => format!(" (`{{{:?}: …}}`)", variant),
+        (Representation::Internal { tag }, Some(variant)) => {
+            format!(" (`{{{:?}: {:?}, …}}`)", tag, variant)
+        }
+        (Representation::Adjacent { tag, content }, Some(variant)) => {
+            format!(" (`{{{:?}: {:?}, {:?}: …}}`)", tag, variant, content)
+        }
+    };
+    let looks = match actual {
+        Representation::Internal { .. } | Representation::Adjacent { .. } => {
+            format!("value looks tagged with {}", actual)
+        }
+        _ => format!("value looks {}", actual),
+    };
+
+    Some(format!("{}{}; schema expects {}", looks, excerpt, expected))
+}
+
+/// Work out how the enum whose variants are described by the given schemas
+/// is represented, along with the names of its variants.
+fn schema_representation(branches: &[&SchemaObject]) -> Option<(Representation, Vec<String>)> {
+    if branches.is_empty() {
+        return None;
+    }
+
+    if let Some(variants) = branches
+        .iter()
+        .map(|branch| external_variants(branch))
+        .collect::<Option<Vec<_>>>()
+    {
+        return Some((Representation::External, variants.concat()));
+    }
+
+    // A tag is a property that every variant requires and that holds the
+    // variant's name.
+    let tag = branches[0].object.as_ref()?.required.iter().find(|prop| {
+        branches
+            .iter()
+            .all(|branch| tag_values(branch, prop).is_some())
+    });
+    let Some(tag) = tag else {
+        return Some((Representation::Untagged, Vec::new()));
+    };
+
+    let variants = branches
+        .iter()
+        .filter_map(|branch| tag_values(branch, tag))
+        .flatten()
+        .collect();
+
+    // With adjacent tagging, variants with data have exactly one other
+    // property, and it has the same name in all of them. A lone variant with
+    // data is indistinguishable from an internally tagged struct variant with
+    // one field, so we need at least two to tell.
+    let others = branches
+        .iter()
+        .map(|branch| {
+            branch
+                .object
+                .iter()
+                .flat_map(|object| object.properties.keys())
+                .filter(|prop| *prop != tag)
+                .collect::<Vec<_>>()
+        })
+        .filter(|props| !props.is_empty())
+        .collect::<Vec<_>>();
+    let content = others.first().and_then(|props| props.first().copied());
+    let adjacent = others.len() > 1
+        && content.is_some_and(|content| others.iter().all(|props| props == &[content]));
+
+    let representation = match content {
+        Some(content) if adjacent => Representation::Adjacent {
+            tag: tag.clone(),
+            content: content.clone(),
+        },
+        _ => Representation::Internal { tag: tag.clone() },
+    };
+
+    Some((representation, variants))
+}
+
+/// The variant names for an externally tagged variant's schema: either a
+/// string for a unit variant or an object with a single property.
+fn external_variants(branch: &SchemaObject) -> Option<Vec<String>> {
+    if let Some(names) = string_values(branch) {
+        return Some(names);
+    }
+
+    let object = branch.object.as_ref()?;
+    match (
+        object.required.len(),
+        object.properties.len(),
+        object.additional_properties.as_deref(),
+    ) {
+        (1, 1, Some(Schema::Bool(false))) => Some(object.required.iter().cloned().collect()),
+        _ => None,
+    }
+}
+
+fn tag_values(branch: &SchemaObject, tag: &str) -> Option<Vec<String>> {
+    let object = branch.object.as_ref()?;
+    if !object.required.contains(tag) {
+        return None;
+    }
+    match object.properties.get(tag)? {
+        Schema::Object(prop_schema) => string_values(prop_schema),
+        Schema::Bool(_) => None,
+    }
+}
+
+fn string_values(schema: &SchemaObject) -> Option<Vec<String>> {
+    let values = match (&schema.const_value, &schema.enum_values) {
+        (Some(value), None) => std::slice::from_ref(value),
+        (None, Some(values)) => values.as_slice(),
+        _ => return None,
+    };
+    values
+        .iter()
+        .map(|value| value.as_str().map(String::from))
+        .collect()
+}
+
+/// Work out how a value represents an enum variant, preferring the
+/// representation that the schema expects if the value is consistent with
+/// it.
+fn value_representation(
+    value: &Value,
+    expected: &Representation,
+    variants: &[String],
+) -> (Representation, Option<String>) {
+    let map = match value {
+        Value::String(name) => return (Representation::External, Some(name.clone())),
+        Value::Object(map) => map,
+        _ => return (Representation::Untagged, None),
+    };
+
+    let expected_tag = match expected {
+        Representation::Internal { tag } | Representation::Adjacent { tag, .. } => Some(tag),
+        _ => None,
+    };
+    let tag = expected_tag
+        .filter(|tag| map.get(*tag).is_some_and(Value::is_string))
+        .or_else(|| {
+            map.iter()
+                .find(|(_, v)| v.as_str().is_some_and(|v| variants.iter().any(|n| n == v)))
+                .map(|(k, _)| k)
+        });
+
+    if let Some(tag) = tag {
+        let variant = map.get(tag).and_then(Value::as_str).map(String::from);
+        let others = map.keys().filter(|k| *k != tag).collect::<Vec<_>>();
+
+        // A unit variant looks the same whether internally or adjacently
+        // tagged, as does a variant with a single field; we give the value
+        // the benefit of the doubt.
+        let representation = match expected {
+            Representation::Adjacent {
+                tag: expected_tag,
+                content,
+            } if expected_tag == tag && others.len() <= 1 => Representation::Adjacent {
+                tag: tag.clone(),
+                content: others.first().copied().unwrap_or(content).clone(),
+            },
+            _ => Representation::Internal { tag: tag.clone() },
+        };
+        return (representation, variant);
+    }
+
+    match map.keys().next() {
+        Some(name) if map.len() == 1 => (Representation::External, Some(name.clone())),
+        _ => (Representation::Untagged, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::JsonSchema;
+    use serde::Serialize;
+
+    use crate::{validate, Error};
+
+    fn details<T: JsonSchema + Serialize>(item: &T) -> String {
+        match validate(item) {
+            Err(Error::InvalidValue { details, .. }) => details,
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_internal_for_external() {
+        #[derive(Serialize, JsonSchema)]
+        #[allow(dead_code)]
+        #[schemars(tag = "kind")]
+        enum Shape {
+            Circle { radius: u32 },
+        }
+
+        assert_eq!(
+            details(&Shape::Circle { radius: 1 }),
+            r#"value looks externally tagged (`{"Circle": …}`); schema expects internal tag `kind`"#,
+        );
+    }
+
+    #[test]
+    fn test_external_for_internal() {
+        #[derive(Serialize, JsonSchema)]
+        #[allow(dead_code)]
+        #[serde(tag = "kind")]
+        #[schemars(tag = "type")]
+        enum Shape {
+            Circle { radius: u32 },
+            Point,
+        }
+
+        assert_eq!(
+            details(&Shape::Point),
+            r#"value looks tagged with internal tag `kind` (`{"kind": "Point", …}`); schema expects internal tag `type`"#,
+        );
+    }
+
+    #[test]
+    fn test_adjacent_content() {
+        #[derive(Serialize, JsonSchema)]
+        #[allow(dead_code)]
+        #[serde(tag = "t", content = "c")]
+        #[schemars(tag = "t", content = "content")]
+        enum Message {
+            Text(String),
+            Count(u32),
+            Ping,
+        }
+
+        assert_eq!(
+            details(&Message::Text("hi".to_string())),
+            r#"value looks tagged with adjacent tag `t` and content `c` (`{"t": "Text", "c": …}`); schema expects adjacent tag `t` and content `content`"#,
+        );
+    }
+
+    #[test]
+    fn test_unknown_variant() {
+        #[derive(Serialize, JsonSchema)]
+        #[allow(dead_code)]
+        #[serde(tag = "kind")]
+        enum Animal {
+            #[schemars(rename = "Feline")]
+            Cat {
+                lives: u32,
+            },
+            Dog {
+                good: bool,
+            },
+        }
+
+        assert_eq!(
+            details(&Animal::Cat { lives: 9 }),
+            "value has the variant Cat, which is not among the schema's variants Feline, Dog",
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
+use serde_json::{Map, Value};
+
+use crate::{display_pointer, escape_pointer_segment, schema::reference_name, Error};
+
+/// Keywords that describe a schema without affecting which values it
+/// permits.
+const METADATA: &[&str] = &["$schema", "title", "description", "examples"];
+
+/// Confirm that two types have structurally equivalent schemas, such as a
+/// hand-written `JsonSchema` implementation and a "shadow" type deriving it.
+///
+/// Both schemas are normalized before they're compared: references are
+/// inlined, metadata (`title`, `description`, and `examples`) is ignored,
+/// `required` and `enum` are sorted, and an `allOf` with a single schema is
+/// replaced by that schema. The error lists each difference along with a
+/// JSON pointer to it within the normalized schemas.
+pub fn check_schemas_equivalent<A: JsonSchema, B: JsonSchema>() -> Result<(), Error> {
+    let a = normalize_root(&schema_for!(A), A::schema_name());
+    let b = normalize_root(&schema_for!(B), B::schema_name());
+
+    let mut differences = Vec::new();
+    diff(String::new(), &a, &b, &mut differences);
+    match differences.is_empty() {
+        true => Ok(()),
+        false => Err(Error::SchemasDiffer {
+            a: A::schema_name(),
+            b: B::schema_name(),
+            differences,
+        }),
+    }
+}
+
+/// Assert that two types have structurally equivalent schemas; see
+/// [`check_schemas_equivalent`].
+#[track_caller]
+pub fn assert_schemas_equivalent<A: JsonSchema, B: JsonSchema>() {
+    if let Err(e) = check_schemas_equivalent::<A, B>() {
+        panic!("{}", e);
+    }
+}
+
+fn normalize_root(root: &RootSchema, name: String) -> Value {
+    let definitions = root
+        .definitions
+        .iter()
+        .map(|(name, schema)| {
+            (
+                name.clone(),
+                serde_json::to_value(schema).unwrap_or_default(),
+            )
+        })
+        .collect::<Map<_, _>>();
+    let schema = serde_json::to_value(&root.schema).unwrap_or_default();
+    normalize(&schema, &definitions, &mut vec![name])
+}
+
+/// Normalize a schema, inlining references to definitions; `inlining` holds
+/// the names of those being inlined, so that a recursive reference can
+/// instead refer to how many levels up its definition was inlined, which
+/// doesn't depend on its name.
+fn normalize(
+    schema: &Value,
+    definitions: &Map<String, Value>,
+    inlining: &mut Vec<String>,
+) -> Value {
+    let map = match schema {
+        Value::Object(map) => map,
+        Value::Array(items) => {
+            return Value::Array(
+                items
+                    .iter()
+                    .map(|item| normalize(item, definitions, inlining))
+                    .collect(),
+            )
+        }
+        other => return other.clone(),
+    };
+
+    let mut out = Map::new();
+    for (key, value) in map {
+        if METADATA.contains(&key.as_str()) || key == "definitions" {
+            continue;
+        }
+        let value = match key.as_str() {
+            // These hold names and values rather than schemas.
+            "required" | "enum" => {
+                let mut items = value.as_array().cloned().unwrap_or_default();
+                items.sort_by_key(|item| item.to_string());
+                Value::Array(items)
+            }
+            "const" => value.clone(),
+            "$ref" => {
+                let name = value.as_str().map(reference_name).unwrap_or_default();
+                match inlining.iter().rposition(|other| other == name) {
+                    Some(level) => Value::String(format!("#recursive/{}", inlining.len() - level)),
+                    None => match definitions.get(name) {
+                        Some(definition) => {
+                            inlining.push(name.to_string());
+                            let inlined = normalize(definition, definitions, inlining);
+                            inlining.pop();
+                            if !merge(&mut out, inlined) {
+                                out.insert(key.clone(), value.clone());
+                            }
+                            continue;
+                        }
+                        None => value.clone(),
+                    },
+                }
+            }
+            "properties" | "patternProperties" => Value::Object(
+                value
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .map(|(name, schema)| (name.clone(), normalize(schema, definitions, inlining)))
+                    .collect(),
+            ),
+            _ => normalize(value, definitions, inlining),
+        };
+        out.insert(key.clone(), value);
+    }
+
+    // An `allOf` of a single schema is just that schema.
+    if let Some(Value::Array(all_of)) = out.get("allOf") {
+        if let [only] = all_of.as_slice() {
+            let only = only.clone();
+            let mut rest = out.clone();
+            rest.remove("allOf");
+            if merge(&mut rest, only) {
+                out = rest;
+            }
+        }
+    }
+
+    Value::Object(out)
+}
+
+/// Merge a schema's keywords into others if none of them conflict.
+fn merge(into: &mut Map<String, Value>, schema: Value) -> bool {
+    let Value::Object(map) = schema else {
+        return false;
+    };
+    if map
+        .iter()
+        .any(|(key, value)| into.get(key).is_some_and(|other| other != value))
+    {
+        return false;
+    }
+    into.extend(map);
+    true
+}
+
+fn diff(pointer: String, a: &Value, b: &Value, out: &mut Vec<String>) {
+    let child = |segment: &str| format!("{}/{}", pointer, escape_pointer_segment(segment));
+    match (a, b) {
+        _ if a == b => (),
+        (Value::Object(a_map), Value::Object(b_map)) => {
+            for (key, a_value) in a_map {
+                match b_map.get(key) {
+                    Some(b_value) => diff(child(key), a_value, b_value, out),
+                    None => out.push(format!("{}: only the first has {}", child(key), a_value)),
+                }
+            }
+            for (key, b_value) in b_map {
+                if !a_map.contains_key(key) {
+                    out.push(format!("{}: only the second has {}", child(key), b_value));
+                }
+            }
+        }
+        (Value::Array(a_items), Value::Array(b_items)) if a_items.len() == b_items.len() => {
+            for (i, (a_item, b_item)) in a_items.iter().zip(b_items).enumerate() {
+                diff(child(&i.to_string()), a_item, b_item, out);
+            }
+        }
+        _ => out.push(format!(
+            "{}: {} in the first but {} in the second",
+            display_pointer(&pointer),
+            a,
+            b
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+    use serde_json::json;
+
+    use crate::{assert_schemas_equivalent, check_schemas_equivalent};
+
+    /// Documents a pet.
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Shadow {
+        name: String,
+        kind: Kind,
+        friends: Vec<Shadow>,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    enum Kind {
+        Cat,
+        Dog,
+    }
+
+    struct Pet;
+
+    impl JsonSchema for Pet {
+        fn schema_name() -> String {
+            "Pet".to_string()
+        }
+
+        fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+            let friends = gen.subschema_for::<Vec<Pet>>();
+            serde_json::from_value(json!({
+                "type": "object",
+                "required": ["name", "friends", "kind"],
+                "properties": {
+                    "name": { "type": "string" },
+                    "kind": { "type": "string", "enum": ["Dog", "Cat"] },
+                    "friends": friends,
+                }
+            }))
+            .unwrap()
+        }
+    }
+
+    struct Stale;
+
+    impl JsonSchema for Stale {
+        fn schema_name() -> String {
+            "Stale".to_string()
+        }
+
+        fn json_schema(_: &mut SchemaGenerator) -> Schema {
+            serde_json::from_value(json!({
+                "type": "object",
+                "required": ["name", "kind"],
+                "properties": {
+                    "name": { "type": "integer" },
+                    "kind": { "type": "string", "enum": ["Cat", "Dog", "Fish"] },
+                }
+            }))
+            .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_equivalent() {
+        assert_schemas_equivalent::<Pet, Shadow>();
+
+        assert_eq!(
+            check_schemas_equivalent::<Shadow, Stale>()
+                .unwrap_err()
+                .to_string(),
+            r##"the schemas for Shadow and Stale differ:
+  /properties/friends: only the first has {"items":{"$ref":"#recursive/1"},"type":"array"}
+  /properties/kind/enum: ["Cat","Dog"] in the first but ["Cat","Dog","Fish"] in the second
+  /properties/name/type: "string" in the first but "integer" in the second
+  /required: ["friends","kind","name"] in the first but ["kind","name"] in the second"##,
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use schemars::{
+    schema::{RootSchema, Schema, SchemaObject},
+    schema_for, JsonSchema,
+};
+use serde::{
+    de::{self, DeserializeOwned, Visitor},
+    forward_to_deserialize_any, Deserializer, Serialize,
+};
+use serde_json::Value;
+
+use crate::{schema::reference_name, validate, Error};
+
+/// Confirm that the values a schema enumerates and the variants of a type
+/// correspond, for fieldless enums and newtypes around them.
+///
+/// Each value the schema's `enum`, `const`, or `allOf` / `oneOf` / `anyOf`
+/// of those permits is deserialized into `T`, serialized back, and checked to be the
+/// same value and to [`validate`]. Variants of `T` that the schema doesn't
+/// list are reported too, as is a `T` with neither, so that the check can't
+/// pass vacuously for a type that isn't an enum. Returns every problem found.
+pub fn validate_enum_exhaustive<T: JsonSchema + Serialize + DeserializeOwned>(
+) -> Result<(), Vec<Error>> {
+    let root = schema_for!(T);
+    let mut values = Vec::new();
+    let mut names = Vec::new();
+    enumerate(&root, &root.schema, &mut values, &mut names);
+    let variants = variants::<T>();
+    if values.is_empty() && variants.is_empty() {
+        return Err(vec![Error::NothingEnumerated {
+            type_name: T::schema_name(),
+        }]);
+    }
+
+    let mut errors = Vec::new();
+    for value in &values {
+        let item = match T::deserialize(value) {
+            Ok(item) => item,
+            Err(source) => {
+                errors.push(Error::UndeserializableVariant {
+                    value: value.clone(),
+                    source,
+                });
+                continue;
+            }
+        };
+        match serde_json::to_value(&item) {
+            Ok(serialized) if serialized != *value => errors.push(Error::VariantRoundTrip {
+                value: value.clone(),
+                serialized,
+            }),
+            Ok(_) => errors.extend(validate(&item).err()),
+            Err(e) => errors.push(e.into()),
+        }
+    }
+
+    for variant in variants {
+        if !names.iter().any(|name| name == variant) {
+            errors.push(Error::UnlistedVariant {
+                variant: variant.to_string(),
+            });
+        }
+    }
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+/// Gather the values a schema enumerates, along with the names of the
+/// variants it describes: those values that are strings and the single
+/// property of each externally tagged variant with data.
+fn enumerate(
+    root: &RootSchema,
+    schema: &SchemaObject,
+    values: &mut Vec<Value>,
+    names: &mut Vec<String>,
+) {
+    let enumerated = schema
+        .const_value
+        .iter()
+        .chain(schema.enum_values.iter().flatten());
+    for value in enumerated {
+        names.extend(value.as_str().map(String::from));
+        values.push(value.clone());
+    }
+
+    if let Some(object) = &schema.object {
+        if let (1, 1) = (object.required.len(), object.properties.len()) {
+            names.extend(object.required.iter().cloned());
+        }
+    }
+
+    let reference = schema
+        .reference
+        .as_ref()
+        .and_then(|reference| root.definitions.get(reference_name(reference)));
+    let branches = schema
+        .subschemas
+        .iter()
+        .flat_map(|subschemas| [&subschemas.all_of, &subschemas.one_of, &subschemas.any_of])
+        .flatten()
+        .flatten();
+    for sub in reference.into_iter().chain(branches) {
+        if let Schema::Object(sub) = sub {
+            enumerate(root, sub, values, names);
+        }
+    }
+}
+
+/// The names of the variants of an enum (or of the enum within a newtype)
+/// as serde knows them, if `T` is one.
+fn variants<T: DeserializeOwned>() -> &'static [&'static str] {
+    let mut variants: &'static [&'static str] = &[];
+    let _ = T::deserialize(VariantNames {
+        variants: &mut variants,
+    });
+    variants
+}
+
+/// A deserializer that records the variants it's asked for and then gives
+/// up.
+struct VariantNames<'a> {
+    variants: &'a mut &'static [&'static str],
+}
+
+impl<'de> Deserializer<'de> for VariantNames<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not an enum"))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        variants: &'static [&'static str],
+        _: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.variants = variants;
+        Err(de::Error::custom("variants recorded"))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct seq tuple tuple_struct map
+        struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+
+    use crate::{validate_enum_exhaustive, Error};
+
+    #[test]
+    fn test_exhaustive() {
+        #[derive(Serialize, Deserialize, JsonSchema)]
+        #[serde(rename_all = "lowercase")]
+        enum Size {
+            Small,
+            Large,
+        }
+
+        #[derive(Serialize, Deserialize, JsonSchema)]
+        struct Wrapper(Size);
+
+        assert!(validate_enum_exhaustive::<Size>().is_ok());
+        assert!(validate_enum_exhaustive::<Wrapper>().is_ok());
+
+        #[derive(Serialize, Deserialize, JsonSchema)]
+        struct Plain {
+            size: Size,
+        }
+
+        // Neither is an enum, so there's nothing to check.
+        assert!(matches!(
+            validate_enum_exhaustive::<String>().unwrap_err().as_slice(),
+            [Error::NothingEnumerated { .. }]
+        ));
+        assert_eq!(
+            validate_enum_exhaustive::<Plain>()
+                .unwrap_err()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["the schema for Plain enumerates no values and the type has no variants"],
+        );
+
+        #[derive(Serialize, Deserialize, JsonSchema)]
+        enum Color {
+            Red,
+            #[schemars(rename = "Grey")]
+            Gray,
+        }
+
+        let errors = validate_enum_exhaustive::<Color>().unwrap_err();
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "the schema lists \"Grey\", which doesn't deserialize: unknown variant `Grey`, \
+                expected `Red` or `Gray`",
+                "the type has the variant Gray, which the schema doesn't list",
+            ],
+        );
+    }
+}
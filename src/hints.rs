@@ -0,0 +1,217 @@
+use schemars::schema::{ObjectValidation, SchemaObject};
+use serde_json::Value;
+
+use crate::ErrorKind;
+
+/// Suggest likely serde causes for a value failing to satisfy a schema.
+pub fn suggest(kind: ErrorKind, schema: &SchemaObject, value: &Value) -> Vec<String> {
+    match kind {
+        ErrorKind::Required => missing_required(schema, value),
+        ErrorKind::Const | ErrorKind::Enum => enumerated_value(schema, value),
+        ErrorKind::AdditionalProperties => additional_property(schema, value),
+        _ => None,
+    }
+    .into_iter()
+    .collect()
+}
+
+pub fn display(hints: &[String]) -> String {
+    hints
+        .iter()
+        .map(|hint| format!("\nhint: {}", hint))
+        .collect()
+}
+
+enum Candidate<'a> {
+    /// The same name save for case and word separators.
+    Folded(&'a str),
+    /// A name within a small edit distance.
+    Near(&'a str),
+}
+
+/// Find the candidate most likely to have been intended in place of `name`.
+fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<Candidate<'a>> {
+    let folded = fold(name);
+    let threshold = (folded.len() / 3).max(1);
+
+    let mut near = None;
+    for candidate in candidates {
+        let candidate_folded = fold(candidate);
+        if candidate_folded == folded {
+            return Some(Candidate::Folded(candidate));
+        }
+        let distance = strsim::levenshtein(&folded, &candidate_folded);
+        if distance <= threshold && near.is_none_or(|(best, _)| distance < best) {
+            near = Some((distance, candidate));
+        }
+    }
+
+    near.map(|(_, candidate)| Candidate::Near(candidate))
+}
+
+/// Normalize a name for comparison regardless of its casing convention.
+fn fold(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn missing_required(schema: &SchemaObject, value: &Value) -> Option<String> {
+    let ObjectValidation {
+        required,
+        properties,
+        ..
+    } = schema.object.as_deref()?;
+    let map = value.as_object()?;
+    let missing = required.iter().find(|prop| !map.contains_key(*prop))?;
+
+    // Only properties the schema doesn't know about could have been
+    // intended as the missing one.
+    let undeclared = map
+        .keys()
+        .filter(|prop| !properties.contains_key(*prop))
+        .map(String::as_str);
+
+    Some(match closest(missing, undeclared) {
+        Some(Candidate::Folded(prop)) => format!(
+            "the value has a property {} instead; do serde and schemars agree on \
+            `rename_all`?",
+            prop,
+        ),
+        Some(Candidate::Near(prop)) => format!(
+            "did you mean {}? do serde and schemars agree on `rename`?",
+            prop
+        ),
+        None => format!(
+            "if {} is skipped with `skip_serializing_if`, it also needs \
+            `#[serde(default)]` so that the schema doesn't require it",
+            missing,
+        ),
+    })
+}
+
+fn enumerated_value(schema: &SchemaObject, value: &Value) -> Option<String> {
+    let s = value.as_str()?;
+    let allowed = schema
+        .const_value
+        .iter()
+        .chain(schema.enum_values.iter().flatten())
+        .filter_map(Value::as_str);
+
+    Some(match closest(s, allowed)? {
+        Candidate::Folded(name) => format!(
+            "{:?} differs only in case or word separators; do serde and schemars \
+            agree on `rename_all`?",
+            name,
+        ),
+        Candidate::Near(name) => format!("did you mean {:?}?", name),
+    })
+}
+
+fn additional_property(schema: &SchemaObject, value: &Value) -> Option<String> {
+    let ObjectValidation { properties, .. } = schema.object.as_deref()?;
+    let map = value.as_object()?;
+    let extra = map.keys().find(|prop| !properties.contains_key(*prop))?;
+
+    let absent = properties
+        .keys()
+        .filter(|prop| !map.contains_key(*prop))
+        .map(String::as_str);
+
+    Some(match closest(extra, absent) {
+        Some(Candidate::Folded(prop)) => format!(
+            "the schema has a property {} instead; do serde and schemars agree on \
+            `rename_all`?",
+            prop,
+        ),
+        Some(Candidate::Near(prop)) => format!(
+            "did you mean {}? do serde and schemars agree on `rename`?",
+            prop
+        ),
+        None => format!(
+            "if {} comes from a `#[serde(flatten)]` field, the schema must include \
+            its properties or permit `additionalProperties`",
+            extra,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::JsonSchema;
+    use serde::Serialize;
+
+    use crate::{validate, Error};
+
+    fn hints<T: JsonSchema + Serialize>(item: &T) -> Vec<String> {
+        match validate(item) {
+            Err(Error::InvalidValue { hints, .. }) => hints,
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rename_all() {
+        #[derive(Serialize, JsonSchema)]
+        #[serde(rename_all = "snake_case")]
+        #[schemars(rename_all = "camelCase")]
+        struct Widget {
+            foo_bar: u32,
+        }
+
+        assert_eq!(
+            hints(&Widget { foo_bar: 1 }),
+            vec![
+                "the value has a property foo_bar instead; do serde and schemars agree on \
+                `rename_all`?"
+            ],
+        );
+    }
+
+    #[test]
+    fn test_enum_value() {
+        #[derive(Serialize, JsonSchema)]
+        enum Color {
+            #[schemars(rename = "Grey")]
+            Gray,
+        }
+
+        assert_eq!(hints(&Color::Gray), vec![r#"did you mean "Grey"?"#]);
+    }
+
+    #[test]
+    fn test_skip_serializing_if() {
+        #[derive(Serialize, JsonSchema)]
+        struct Tagged {
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            tags: Vec<String>,
+        }
+
+        assert_eq!(
+            hints(&Tagged { tags: Vec::new() }),
+            vec![
+                "if tags is skipped with `skip_serializing_if`, it also needs \
+                `#[serde(default)]` so that the schema doesn't require it"
+            ],
+        );
+    }
+
+    #[test]
+    fn test_additional_property() {
+        #[derive(Serialize, JsonSchema)]
+        #[serde(deny_unknown_fields)]
+        struct Paint {
+            #[schemars(rename = "colour")]
+            #[serde(skip_serializing_if = "Option::is_none")]
+            color: Option<String>,
+        }
+
+        assert_eq!(
+            hints(&Paint {
+                color: Some("red".to_string())
+            }),
+            vec!["did you mean colour? do serde and schemars agree on `rename`?"],
+        );
+    }
+}
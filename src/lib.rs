@@ -1,23 +1,451 @@
-use schema::validate_schema_object;
-use schemars::{schema::RootSchema, schema_for, JsonSchema};
+// Errors carry the offending value along with context for diagnosing it;
+// validation is not on any hot path, so their size is of no concern.
+#![allow(clippy::result_large_err)]
+
+use schema::{validate_schema_object, Context};
+use schemars::{
+    schema::{RootSchema, Schema},
+    schema_for, JsonSchema,
+};
 use serde::Serialize;
 use serde_json::Value;
 use thiserror::Error;
+use tree::Ordered;
 
+mod bytes;
+mod content;
+mod cross;
+mod deterministic;
+#[cfg(feature = "miette")]
+mod diagnostic;
+mod enums;
+mod equivalence;
+mod exhaustive;
+mod hints;
+mod metadata;
+mod minimize;
+mod mutate;
+mod numbers;
+mod rejected;
+mod report;
 mod schema;
+mod serialize;
+mod subschema;
+mod tree;
+mod warning;
+
+pub use cross::{validate_cross, validate_cross_round_trip};
+pub use deterministic::{validate_deterministic, validate_deterministic_clones};
+#[cfg(feature = "miette")]
+pub use diagnostic::Failure;
+pub use equivalence::{assert_schemas_equivalent, check_schemas_equivalent};
+pub use exhaustive::validate_enum_exhaustive;
+pub use metadata::{
+    validate_default_impl, validate_defaults, validate_examples, validate_examples_for,
+};
+pub use minimize::minimize;
+pub use mutate::validate_mutants;
+pub use rejected::{assert_rejected, check_rejected};
+pub use report::{annotate, report, Verbosity};
+pub use subschema::{is_subschema, Incompatibility};
+pub use warning::{Warning, WarningKind};
 
 #[derive(Error, Debug)]
 pub enum Error {
-    #[error("error serializing item")]
-    SerializationError(#[from] serde_json::Error),
+    #[error(
+        "error serializing item{}: {source}{}",
+        display_at(.path),
+        display_expectation(.expectation)
+    )]
+    SerializationError {
+        /// Where serialization failed, such as `$.items[3].kind`, if known.
+        path: Option<String>,
+        source: serde_json::Error,
+        /// What the schema expects there.
+        expectation: Option<String>,
+    },
     #[error("invalid schema at {path}: {details}")]
     InvalidSchema { path: String, details: String },
-    #[error("{value} did not conform to the schema at {path}: {details}")]
+    #[error(
+        "{value} did not conform to the schema at {path}{}: {details}{}",
+        display_types(.types),
+        hints::display(.hints)
+    )]
     InvalidValue {
         path: String,
+        /// A JSON pointer to the value within the serialized item.
+        pointer: String,
+        value: Value,
+        /// The subschema the value failed to satisfy.
+        schema: Box<Schema>,
+        details: String,
+        kind: ErrorKind,
+        /// The names of the Rust types whose schemas were traversed to reach
+        /// the value, outermost first.
+        types: Vec<String>,
+        /// Suggestions as to what might have caused the error.
+        hints: Vec<String>,
+    },
+    #[error("{0}")]
+    PromotedWarning(Warning),
+    #[error("the serialized output is not valid JSON: {0}")]
+    InvalidOutput(serde_json::Error),
+    #[error(
+        "the serialized object at {} has the key {key} more than once",
+        display_pointer(.pointer)
+    )]
+    DuplicateKey { pointer: String, key: String },
+    #[error(
+        "serializing to a value and to bytes disagree at {}: {details}",
+        display_pointer(.pointer)
+    )]
+    ValueMismatch { pointer: String, details: String },
+    #[error(
+        "{path} is {}, which serde_json writes as null",
+        display_non_finite(*.value)
+    )]
+    NonFiniteFloat { path: String, value: f64 },
+    #[error(
+        "{path} is {value}, which exceeds 64 bits; serde_json can only serialize it \
+        with the `arbitrary_precision` feature"
+    )]
+    IntegerOutOfRange { path: String, value: String },
+    #[error("{path} is {value}, which reads back from JSON as {read}")]
+    FloatRoundTrip {
+        path: String,
+        value: String,
+        read: String,
+    },
+    #[error(
+        "serialization run {run} differs from the first at {}: {details}",
+        display_pointer(.pointer)
+    )]
+    Nondeterministic {
+        run: usize,
+        pointer: String,
+        details: String,
+    },
+    #[error("the example at {location} doesn't conform to its schema: {error}")]
+    InvalidExample {
+        /// A JSON pointer to the example within the schema document.
+        location: String,
+        error: Box<Error>,
+    },
+    #[error("the example at {location} doesn't deserialize: {source}")]
+    UndeserializableExample {
+        location: String,
+        source: serde_json::Error,
+    },
+    #[error("the schema has no definition for {name}")]
+    MissingDefinition { name: String },
+    #[error("the default at {location} doesn't conform to its schema: {error}")]
+    InvalidDefault {
+        /// A JSON pointer to the default within the schema document.
+        location: String,
+        error: Box<Error>,
+    },
+    #[error(
+        "the schema documents the default for {property} as {documented}, but serde \
+        produces {actual} when it's missing"
+    )]
+    DefaultMismatch {
+        property: String,
+        documented: Value,
+        actual: Value,
+    },
+    #[error("{mutation} at {}: {details}", display_pointer(.pointer))]
+    MutantDisagreement {
+        /// A JSON pointer to the changed value within the serialized item.
+        pointer: String,
+        /// The change, such as "removing the optional property name".
+        mutation: String,
+        details: String,
+    },
+    #[error("the schema lists {value}, which doesn't deserialize: {source}")]
+    UndeserializableVariant {
         value: Value,
+        source: serde_json::Error,
+    },
+    #[error("the schema lists {value}, which serializes back as {serialized}")]
+    VariantRoundTrip { value: Value, serialized: Value },
+    #[error("the type has the variant {variant}, which the schema doesn't list")]
+    UnlistedVariant { variant: String },
+    #[error("the schema for {type_name} enumerates no values and the type has no variants")]
+    NothingEnumerated { type_name: String },
+    #[error("{value} should be rejected, but {details}")]
+    NotRejected { value: Value, details: String },
+    #[error("the value doesn't deserialize into {target}: {source}")]
+    CrossDeserialization {
+        target: String,
+        source: serde_json::Error,
+    },
+    #[error(
+        "serializing the value as {target} differs from the original at {}: {details}",
+        display_pointer(.pointer)
+    )]
+    CrossMismatch {
+        target: String,
+        pointer: String,
         details: String,
     },
+    #[error(
+        "the schemas for {a} and {b} differ:{}",
+        differences.iter().map(|d| format!("\n  {}", d)).collect::<String>()
+    )]
+    SchemasDiffer {
+        a: String,
+        b: String,
+        /// Each difference, prefixed with a JSON pointer to it within the
+        /// normalized schemas.
+        differences: Vec<String>,
+    },
+}
+
+/// The requirement an invalid value failed to satisfy; generally named for
+/// the relevant schema keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The `false` schema, which no value satisfies.
+    False,
+    Type,
+    Const,
+    Enum,
+    AllOf,
+    AnyOf,
+    OneOf,
+    Not,
+    MultipleOf,
+    Maximum,
+    ExclusiveMaximum,
+    Minimum,
+    ExclusiveMinimum,
+    MaxLength,
+    MinLength,
+    Pattern,
+    MaxItems,
+    MinItems,
+    UniqueItems,
+    Contains,
+    MaxProperties,
+    MinProperties,
+    Required,
+    AdditionalProperties,
+    Dependencies,
+    ContentEncoding,
+    ContentMediaType,
+    ReadOnly,
+    WriteOnly,
+    Deprecated,
+    /// Properties serialized out of the order that
+    /// [`Options::property_order`] calls for.
+    PropertyOrder,
+}
+
+impl ErrorKind {
+    /// A stable identifier for the kind of error, such as `maximum` or
+    /// `missing_required`.
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorKind::False => "false_schema",
+            ErrorKind::Type => "type",
+            ErrorKind::Const => "const",
+            ErrorKind::Enum => "enum",
+            ErrorKind::AllOf => "all_of",
+            ErrorKind::AnyOf => "any_of",
+            ErrorKind::OneOf => "one_of",
+            ErrorKind::Not => "not",
+            ErrorKind::MultipleOf => "multiple_of",
+            ErrorKind::Maximum => "maximum",
+            ErrorKind::ExclusiveMaximum => "exclusive_maximum",
+            ErrorKind::Minimum => "minimum",
+            ErrorKind::ExclusiveMinimum => "exclusive_minimum",
+            ErrorKind::MaxLength => "max_length",
+            ErrorKind::MinLength => "min_length",
+            ErrorKind::Pattern => "pattern",
+            ErrorKind::MaxItems => "max_items",
+            ErrorKind::MinItems => "min_items",
+            ErrorKind::UniqueItems => "unique_items",
+            ErrorKind::Contains => "contains",
+            ErrorKind::MaxProperties => "max_properties",
+            ErrorKind::MinProperties => "min_properties",
+            ErrorKind::Required => "missing_required",
+            ErrorKind::AdditionalProperties => "additional_property",
+            ErrorKind::Dependencies => "missing_dependency",
+            ErrorKind::ContentEncoding => "content_encoding",
+            ErrorKind::ContentMediaType => "content_media_type",
+            ErrorKind::ReadOnly => "read_only",
+            ErrorKind::WriteOnly => "write_only",
+            ErrorKind::Deprecated => "deprecated",
+            ErrorKind::PropertyOrder => "property_order",
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(source: serde_json::Error) -> Self {
+        Error::SerializationError {
+            path: None,
+            source,
+            expectation: None,
+        }
+    }
+}
+
+fn display_at(path: &Option<String>) -> String {
+    match path {
+        Some(path) => format!(" at {}", path),
+        None => String::new(),
+    }
+}
+
+fn display_expectation(expectation: &Option<String>) -> String {
+    match expectation {
+        Some(expectation) => format!("; {}", expectation),
+        None => String::new(),
+    }
+}
+
+fn display_pointer(pointer: &str) -> &str {
+    match pointer {
+        "" => "the root",
+        _ => pointer,
+    }
+}
+
+fn display_non_finite(value: f64) -> &'static str {
+    if value.is_nan() {
+        "NaN"
+    } else if value.is_sign_positive() {
+        "infinity"
+    } else {
+        "-infinity"
+    }
+}
+
+fn display_types(types: &[String]) -> String {
+    match types {
+        [] => String::new(),
+        _ => format!(" (in {})", types.join(" > ")),
+    }
+}
+
+/// Escape a property name for use as a segment of a JSON pointer.
+pub(crate) fn escape_pointer_segment(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+/// The result of [`validate_with_options`]: all errors and warnings found.
+#[derive(Debug, Default)]
+pub struct ValidationOutcome {
+    pub errors: Vec<Error>,
+    pub warnings: Vec<Warning>,
+    /// The serialized item, or `null` if it couldn't be serialized.
+    pub value: Value,
+}
+
+impl ValidationOutcome {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// The first error if there were any, or the warnings otherwise.
+    pub fn into_result(self) -> Result<Vec<Warning>, Error> {
+        match self.errors.into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(self.warnings),
+        }
+    }
+}
+
+/// How to treat a questionable, but not necessarily invalid, finding.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// Say nothing.
+    #[default]
+    Ignore,
+    /// Report a [`Warning`].
+    Warn,
+    /// Fail validation.
+    Error,
+}
+
+/// The direction in which a serialized value travels, which determines
+/// whether `readOnly` and `writeOnly` properties may be present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Output from a server, such as a response body; `writeOnly` properties
+    /// must not appear.
+    Response,
+    /// Input to a server, such as a request body; `readOnly` properties must
+    /// not appear.
+    Request,
+}
+
+/// The order in which an object's properties should be serialized; see
+/// [`Options::property_order`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyOrder {
+    /// The order of the schema's `properties`, which is the order of the
+    /// struct's fields with this crate's `preserve_order` feature; without
+    /// it, schemars sorts properties and this is the same as
+    /// [`PropertyOrder::Alphabetical`].
+    Schema,
+    /// Required properties before optional ones, each in schema order.
+    RequiredFirst,
+    /// Alphabetical order.
+    Alphabetical,
+}
+
+/// Options to tune validation; see [`validate_with_options`].
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// How to treat unknown keywords in a schema's extensions; keywords
+    /// prefixed with `x-` are vendor extensions and are never reported.
+    pub unknown_keywords: Policy,
+    /// Decode strings with a `contentEncoding` (such as `base64`) and check
+    /// that content with a JSON `contentMediaType` parses and, if the schema
+    /// has a `contentSchema`, validates.
+    pub decode_content: bool,
+    /// The direction of the serialized value, if `readOnly` and `writeOnly`
+    /// properties should be checked; defaults to `None`, which permits both.
+    pub direction: Option<Direction>,
+    /// How to treat values that set deprecated properties, match deprecated
+    /// `oneOf` variants, or use deprecated types; defaults to
+    /// [`Policy::Warn`].
+    pub deprecated: Policy,
+    /// Warnings to report as errors ([`Error::PromotedWarning`]) instead.
+    pub promote: Vec<WarningKind>,
+    /// Serialize with `serde_json::to_vec` and parse the output, reporting
+    /// duplicate keys ([`Error::DuplicateKey`]), invalid output
+    /// ([`Error::InvalidOutput`]), and differences from
+    /// `serde_json::to_value` ([`Error::ValueMismatch`]); the parsed output
+    /// is then validated.
+    pub check_bytes: bool,
+    /// Check that each float reads back from its JSON text as the same value
+    /// ([`Error::FloatRoundTrip`]), which serde_json only guarantees with its
+    /// `float_roundtrip` feature. Non-finite floats
+    /// ([`Error::NonFiniteFloat`]) and integers beyond 64 bits
+    /// ([`Error::IntegerOutOfRange`]) are always reported.
+    pub float_round_trip: bool,
+    /// Check the order in which the properties each schema declares are
+    /// written by `serde_json::to_vec`, reporting those out of order as
+    /// errors ([`ErrorKind::PropertyOrder`]).
+    pub property_order: Option<PropertyOrder>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            unknown_keywords: Policy::Ignore,
+            decode_content: false,
+            direction: None,
+            deprecated: Policy::Warn,
+            promote: Vec::new(),
+            check_bytes: false,
+            float_round_trip: false,
+            property_order: None,
+        }
+    }
 }
 
 /// Confirm that an item matches its schema.
@@ -25,27 +453,114 @@ pub enum Error {
 /// The item's type must implement `Serialize` and `JsonSchema`. This function
 /// serializes the item and compares that serialization to the type's schema.
 pub fn validate<T: JsonSchema + Serialize>(item: &T) -> Result<(), Error> {
-    let value = serde_json::to_value(item)?;
+    validate_with_options(item, &Options::default())
+        .into_result()
+        .map(|_| ())
+}
+
+/// Confirm that an item matches its schema using the given options.
+///
+/// Rather than stopping at the first error, this reports errors for every
+/// property and array item that fails, along with any warnings. See
+/// [`validate`].
+pub fn validate_with_options<T: JsonSchema + Serialize>(
+    item: &T,
+    options: &Options,
+) -> ValidationOutcome {
+    let schema = schema_for!(T);
+    let serialized = if options.check_bytes {
+        bytes::serialize(item, &schema, options.float_round_trip)
+            .map(|(value, written, errors)| (value, Some(written), errors))
+    } else {
+        serialize::to_checked_value(item, &schema, options.float_round_trip)
+            .map(|(value, errors)| (value, None, errors))
+    };
+    let (value, written, mut errors) = match serialized {
+        Ok(serialized) => serialized,
+        Err(err) => {
+            return ValidationOutcome {
+                errors: vec![err],
+                ..Default::default()
+            };
+        }
+    };
+
+    // Values don't preserve the order of properties, so get it from the
+    // serialized bytes, unless they were already parsed.
+    let written = match (options.property_order, written) {
+        (Some(_), Some(written)) => Some(written),
+        (Some(_), None) => match serialize::to_vec(item, &schema)
+            .and_then(|bytes| Ordered::parse(&bytes).map_err(Error::InvalidOutput))
+        {
+            Ok(written) => Some(written),
+            Err(err) => {
+                errors.push(err);
+                None
+            }
+        },
+        (None, _) => None,
+    };
 
-    let RootSchema {
-        schema,
-        definitions,
-        ..
-    } = schema_for!(T);
+    let outcome = validate_ordered(&schema, &value, written.as_ref(), options);
+    errors.extend(outcome.errors);
+    ValidationOutcome {
+        errors,
+        value,
+        ..outcome
+    }
+}
+
+/// Validate a value against a schema; the outcome's `value` is left `null`.
+pub(crate) fn validate_value(
+    schema: &RootSchema,
+    value: &Value,
+    options: &Options,
+) -> ValidationOutcome {
+    validate_ordered(schema, value, None, options)
+}
 
-    validate_schema_object("$", &schema, &definitions, &value)
+/// Validate a value against a schema, given the order in which its
+/// properties were written.
+fn validate_ordered(
+    schema: &RootSchema,
+    value: &Value,
+    written: Option<&Ordered>,
+    options: &Options,
+) -> ValidationOutcome {
+    let mut cx = Context::new(&schema.definitions, options);
+    if let Some(written) = written {
+        cx.written = written.property_orders();
+    }
+    let result = validate_schema_object("$", &schema.schema, &cx, value);
+    cx.finish(result)
 }
 
 /// Confirm that an item matches its schema and print on failure.
 ///
-/// See [`validate`].
+/// The output shows the part of the schema that the value failed to match
+/// and an excerpt of the value around the failure; see [`report`]. See
+/// [`validate`].
 pub fn validate_with_output<T: JsonSchema + Serialize>(item: &T) -> Result<(), String> {
+    validate_with_report(item, Verbosity::Focused)
+}
+
+/// Confirm that an item matches its schema and print the entire schema and
+/// value on failure.
+///
+/// See [`validate_with_output`].
+pub fn validate_with_verbose_output<T: JsonSchema + Serialize>(item: &T) -> Result<(), String> {
+    validate_with_report(item, Verbosity::Full)
+}
+
+fn validate_with_report<T: JsonSchema + Serialize>(
+    item: &T,
+    verbosity: Verbosity,
+) -> Result<(), String> {
     validate(item).map_err(|e| {
         let schema = schema_for!(T);
-        format!(
-            "error: {e}\nschema: {}\nvalue: {}",
-            serde_json::to_string_pretty(&schema).unwrap(),
-            serde_json::to_string_pretty(&item).unwrap(),
-        )
+        match serde_json::to_value(item) {
+            Ok(value) => report(&e, &schema, &value, verbosity),
+            Err(_) => format!("error: {e}"),
+        }
     })
 }
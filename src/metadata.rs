@@ -0,0 +1,511 @@
+use schemars::{
+    schema::{InstanceType, RootSchema, Schema, SchemaObject, SingleOrVec},
+    schema_for, JsonSchema,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    escape_pointer_segment,
+    schema::{reference_name, validate_schema_object, Context},
+    validate, Error, Options,
+};
+
+/// Confirm that each of a schema's `examples` conforms to the subschema it
+/// belongs to, and that those for the type as a whole deserialize into it.
+///
+/// This covers the examples of the root schema, of each definition, and of
+/// every subschema within them, such as those of properties. Only the root
+/// schema's examples are deserialized, since the Rust types of definitions
+/// aren't known; use [`validate_examples_for`] for those. Returns every
+/// stale example found.
+pub fn validate_examples<T: JsonSchema + DeserializeOwned>() -> Result<(), Vec<Error>> {
+    check_examples(&schema_for!(T), "#", |example| {
+        T::deserialize(example).err()
+    })
+}
+
+/// Like [`validate_examples`], but for the definition of `U` within the
+/// schema of `T`: confirm that the examples within that definition conform,
+/// and that those for the definition as a whole deserialize into `U`.
+///
+/// It's an error if the schema has no definition named
+/// `U::schema_name()`, as when `U` is inlined rather than referenced.
+pub fn validate_examples_for<T, U>() -> Result<(), Vec<Error>>
+where
+    T: JsonSchema,
+    U: JsonSchema + DeserializeOwned,
+{
+    let root = schema_for!(T);
+    let name = U::schema_name();
+    if !root.definitions.contains_key(&name) {
+        return Err(vec![Error::MissingDefinition { name }]);
+    }
+    let within = format!("#/definitions/{}", escape_pointer_segment(&name));
+    check_examples(&root, &within, |example| U::deserialize(example).err())
+}
+
+/// Check the examples of the subschema at `within` and of those it contains,
+/// deserializing those of the subschema itself.
+fn check_examples(
+    root: &RootSchema,
+    within: &str,
+    deserialize: impl Fn(&Value) -> Option<serde_json::Error>,
+) -> Result<(), Vec<Error>> {
+    let mut errors = Vec::new();
+
+    walk(root, |location, schema| {
+        if location != within && !location.starts_with(&format!("{}/", within)) {
+            return;
+        }
+        let examples = schema.metadata.iter().flat_map(|m| &m.examples);
+        for (i, example) in examples.enumerate() {
+            let example_location = format!("{}/examples/{}", location, i);
+            errors.extend(conform(root, schema, example).into_iter().map(|error| {
+                Error::InvalidExample {
+                    location: example_location.clone(),
+                    error: Box::new(error),
+                }
+            }));
+            if location == within {
+                if let Some(source) = deserialize(example) {
+                    errors.push(Error::UndeserializableExample {
+                        location: example_location,
+                        source,
+                    });
+                }
+            }
+        }
+    });
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+/// Confirm that each `default` in a schema conforms to the subschema it
+/// belongs to, and that the defaults of the type's own fields are what serde
+/// produces when those fields are missing.
+///
+/// To see what serde produces, an object with just the required fields is
+/// deserialized and serialized again; the required fields are given their
+/// examples or the simplest values their schemas permit. If no such object
+/// can be found, or if a field isn't serialized, its default isn't compared.
+/// As with [`validate_examples`], only the fields of `T` itself are
+/// compared. Returns every problem found.
+pub fn validate_defaults<T: JsonSchema + Serialize + DeserializeOwned>() -> Result<(), Vec<Error>> {
+    let root = schema_for!(T);
+    let mut errors = Vec::new();
+
+    walk(&root, |location, schema| {
+        if let Some(default) = schema.metadata.as_ref().and_then(|m| m.default.as_ref()) {
+            let location = format!("{}/default", location);
+            errors.extend(conform(&root, schema, default).into_iter().map(|error| {
+                Error::InvalidDefault {
+                    location: location.clone(),
+                    error: Box::new(error),
+                }
+            }));
+        }
+    });
+
+    errors.extend(field_defaults::<T>(&root));
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+/// Like [`validate_defaults`], and also [`validate`] `T::default()`.
+pub fn validate_default_impl<T>() -> Result<(), Vec<Error>>
+where
+    T: JsonSchema + Serialize + DeserializeOwned + Default,
+{
+    let mut errors = validate_defaults::<T>().err().unwrap_or_default();
+    errors.extend(validate(&T::default()).err());
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+/// Compare the defaults that the root schema documents for its properties
+/// with those serde produces.
+fn field_defaults<T: Serialize + DeserializeOwned>(root: &RootSchema) -> Vec<Error> {
+    let Some(object) = &root.schema.object else {
+        return Vec::new();
+    };
+    let Some(Value::Object(required)) = sample(root, &root.schema, 0) else {
+        return Vec::new();
+    };
+    if !conform(root, &root.schema, &Value::Object(required.clone())).is_empty() {
+        return Vec::new();
+    }
+    let Ok(item) = serde_json::from_value::<T>(Value::Object(required.clone())) else {
+        return Vec::new();
+    };
+    let Ok(Value::Object(produced)) = serde_json::to_value(&item) else {
+        return Vec::new();
+    };
+
+    object
+        .properties
+        .iter()
+        .filter(|(prop, _)| !required.contains_key(*prop))
+        .filter_map(|(prop, prop_schema)| {
+            let Schema::Object(prop_schema) = prop_schema else {
+                return None;
+            };
+            let documented = prop_schema.metadata.as_ref()?.default.as_ref()?;
+            let actual = produced.get(prop)?;
+            (actual != documented).then(|| Error::DefaultMismatch {
+                property: prop.clone(),
+                documented: documented.clone(),
+                actual: actual.clone(),
+            })
+        })
+        .collect()
+}
+
+/// A simple value that a schema permits, if one is easily found; objects
+/// get only their required properties.
+pub fn sample(root: &RootSchema, schema: &SchemaObject, depth: usize) -> Option<Value> {
+    // Recursive types needn't be explored far to find a simple value.
+    if depth > 16 {
+        return None;
+    }
+    let sample_schema = |schema: &Schema| match schema {
+        Schema::Bool(true) => Some(Value::Null),
+        Schema::Bool(false) => None,
+        Schema::Object(obj) => sample(root, obj, depth + 1),
+    };
+
+    if let Some(example) = schema.metadata.iter().flat_map(|m| &m.examples).next() {
+        return Some(example.clone());
+    }
+    if let Some(value) = &schema.const_value {
+        return Some(value.clone());
+    }
+    if let Some(values) = &schema.enum_values {
+        return values.first().cloned();
+    }
+    if let Some(reference) = &schema.reference {
+        return sample_schema(root.definitions.get(reference_name(reference))?);
+    }
+    if let Some(subschemas) = &schema.subschemas {
+        match (&subschemas.all_of, &subschemas.any_of, &subschemas.one_of) {
+            (Some(all_of), None, None) if all_of.len() == 1 => return sample_schema(&all_of[0]),
+            (None, Some(branches), None) | (None, None, Some(branches)) => {
+                return branches.iter().find_map(sample_schema)
+            }
+            _ => return None,
+        }
+    }
+
+    let instance_type = match schema.instance_type.as_ref()? {
+        SingleOrVec::Single(instance_type) => **instance_type,
+        SingleOrVec::Vec(instance_types) => *instance_types.first()?,
+    };
+    match instance_type {
+        InstanceType::Null => Some(Value::Null),
+        InstanceType::Boolean => Some(Value::Bool(false)),
+        InstanceType::Integer | InstanceType::Number => {
+            let minimum = schema.number.as_ref().and_then(|n| n.minimum);
+            Some(serde_json::json!(minimum.unwrap_or(0.0).max(0.0) as i64))
+        }
+        InstanceType::String => Some(Value::String(String::new())),
+        InstanceType::Array => Some(Value::Array(Vec::new())),
+        InstanceType::Object => {
+            let Some(object) = &schema.object else {
+                return Some(Value::Object(Map::new()));
+            };
+            object
+                .required
+                .iter()
+                .map(|prop| Some((prop.clone(), sample_schema(object.properties.get(prop)?)?)))
+                .collect::<Option<Map<_, _>>>()
+                .map(Value::Object)
+        }
+    }
+}
+
+/// Validate a value against a subschema of the given root.
+pub fn conform(root: &RootSchema, schema: &SchemaObject, value: &Value) -> Vec<Error> {
+    let options = Options::default();
+    let cx = Context::new(&root.definitions, &options);
+    let result = validate_schema_object("$", schema, &cx, value);
+    cx.finish(result).errors
+}
+
+/// Visit the root schema, each definition, and every subschema within them
+/// along with its location as a JSON pointer within the schema document
+/// (e.g. `#/definitions/Pet/properties/name`).
+pub fn walk<'a>(root: &'a RootSchema, mut visit: impl FnMut(&str, &'a SchemaObject)) {
+    walk_object("#".to_string(), &root.schema, &mut visit);
+    for (name, definition) in &root.definitions {
+        walk_schema(
+            format!("#/definitions/{}", escape_pointer_segment(name)),
+            definition,
+            &mut visit,
+        );
+    }
+}
+
+fn walk_schema<'a>(
+    location: String,
+    schema: &'a Schema,
+    visit: &mut impl FnMut(&str, &'a SchemaObject),
+) {
+    if let Schema::Object(obj) = schema {
+        walk_object(location, obj, visit);
+    }
+}
+
+fn walk_object<'a>(
+    location: String,
+    schema: &'a SchemaObject,
+    visit: &mut impl FnMut(&str, &'a SchemaObject),
+) {
+    visit(&location, schema);
+
+    if let Some(subschemas) = &schema.subschemas {
+        for (keyword, list) in [
+            ("allOf", &subschemas.all_of),
+            ("anyOf", &subschemas.any_of),
+            ("oneOf", &subschemas.one_of),
+        ] {
+            for (i, sub) in list.iter().flatten().enumerate() {
+                walk_schema(format!("{}/{}/{}", location, keyword, i), sub, visit);
+            }
+        }
+        for (keyword, sub) in [
+            ("not", &subschemas.not),
+            ("if", &subschemas.if_schema),
+            ("then", &subschemas.then_schema),
+            ("else", &subschemas.else_schema),
+        ] {
+            if let Some(sub) = sub {
+                walk_schema(format!("{}/{}", location, keyword), sub, visit);
+            }
+        }
+    }
+
+    if let Some(array) = &schema.array {
+        match &array.items {
+            Some(SingleOrVec::Single(items)) => {
+                walk_schema(format!("{}/items", location), items, visit)
+            }
+            Some(SingleOrVec::Vec(items)) => {
+                for (i, item) in items.iter().enumerate() {
+                    walk_schema(format!("{}/items/{}", location, i), item, visit);
+                }
+            }
+            None => (),
+        }
+        for (keyword, sub) in [
+            ("additionalItems", &array.additional_items),
+            ("contains", &array.contains),
+        ] {
+            if let Some(sub) = sub {
+                walk_schema(format!("{}/{}", location, keyword), sub, visit);
+            }
+        }
+    }
+
+    if let Some(object) = &schema.object {
+        for (name, prop) in &object.properties {
+            walk_schema(
+                format!("{}/properties/{}", location, escape_pointer_segment(name)),
+                prop,
+                visit,
+            );
+        }
+        for (pattern, prop) in &object.pattern_properties {
+            walk_schema(
+                format!(
+                    "{}/patternProperties/{}",
+                    location,
+                    escape_pointer_segment(pattern)
+                ),
+                prop,
+                visit,
+            );
+        }
+        for (keyword, sub) in [
+            ("additionalProperties", &object.additional_properties),
+            ("propertyNames", &object.property_names),
+        ] {
+            if let Some(sub) = sub {
+                walk_schema(format!("{}/{}", location, keyword), sub, visit);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+    use serde_json::{json, Value};
+
+    use crate::{
+        validate_default_impl, validate_defaults, validate_examples, validate_examples_for, Error,
+    };
+
+    fn pet() -> Value {
+        json!({ "name": "Rex", "legs": 4 })
+    }
+
+    fn stale_pet() -> Value {
+        json!({ "name": "Rex", "leg": 4 })
+    }
+
+    fn name() -> Value {
+        json!("Rex")
+    }
+
+    fn stale_legs() -> Value {
+        json!(-1)
+    }
+
+    #[test]
+    fn test_examples() {
+        #[derive(Deserialize, JsonSchema)]
+        #[allow(dead_code)]
+        #[schemars(example = "pet")]
+        struct Pet {
+            #[schemars(example = "name")]
+            name: String,
+            legs: u32,
+        }
+
+        assert!(validate_examples::<Pet>().is_ok());
+
+        #[derive(Deserialize, JsonSchema)]
+        #[allow(dead_code)]
+        #[schemars(example = "stale_pet")]
+        struct Stale {
+            #[schemars(example = "name")]
+            name: String,
+            #[schemars(example = "stale_legs")]
+            legs: u32,
+        }
+
+        let errors = validate_examples::<Stale>().unwrap_err();
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                r#"the example at #/examples/0 doesn't conform to its schema: {"leg":4,"name":"Rex"} did not conform to the schema at $ (in Stale): the property legs is required but absent
+hint: did you mean leg? do serde and schemars agree on `rename`?"#,
+                "the example at #/examples/0 doesn't deserialize: missing field `legs`",
+                "the example at #/properties/legs/examples/0 doesn't conform to its schema: -1 \
+                did not conform to the schema at $: the value -1 < the minimum 0",
+            ],
+        );
+        assert!(matches!(errors[0], Error::InvalidExample { .. }));
+    }
+
+    fn owner() -> Value {
+        json!({ "name": "Ann", "since": "2020" })
+    }
+
+    #[test]
+    fn test_examples_for() {
+        #[derive(Deserialize, JsonSchema)]
+        #[allow(dead_code)]
+        #[schemars(example = "owner")]
+        struct Owner {
+            name: String,
+            since: u32,
+        }
+
+        #[derive(Deserialize, JsonSchema)]
+        #[allow(dead_code)]
+        struct Kennel {
+            owner: Owner,
+            backup: Owner,
+        }
+
+        // Only the root schema's examples are deserialized.
+        let errors = validate_examples::<Kennel>().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            Error::InvalidExample { location, .. } if location == "#/definitions/Owner/examples/0"
+        ));
+
+        let errors = validate_examples_for::<Kennel, Owner>().unwrap_err();
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                r#"the example at #/definitions/Owner/examples/0 doesn't conform to its schema: "2020" did not conform to the schema at $.since: value is not of type Integer"#,
+                "the example at #/definitions/Owner/examples/0 doesn't deserialize: invalid type: \
+                string \"2020\", expected u32",
+            ],
+        );
+
+        let errors = validate_examples_for::<Kennel, String>().unwrap_err();
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["the schema has no definition for String"],
+        );
+    }
+
+    fn two() -> u32 {
+        2
+    }
+
+    fn default_legs() -> u32 {
+        4
+    }
+
+    #[test]
+    fn test_defaults() {
+        #[derive(Serialize, Deserialize, JsonSchema, Default)]
+        struct Settings {
+            name: String,
+            #[serde(default = "two")]
+            retries: u32,
+            #[serde(default)]
+            verbose: bool,
+        }
+
+        assert!(validate_default_impl::<Settings>().is_ok());
+
+        #[derive(Serialize, Deserialize, JsonSchema)]
+        struct Stale {
+            name: String,
+            // The schema documents 4, but serde produces 2.
+            #[serde(default = "two")]
+            #[schemars(default = "default_legs")]
+            legs: u32,
+            #[schemars(default = "negative")]
+            #[serde(default)]
+            count: u32,
+        }
+
+        fn negative() -> i32 {
+            -1
+        }
+
+        let errors = validate_defaults::<Stale>().unwrap_err();
+        // Properties are in field order with the `preserve_order` feature.
+        let mut messages = errors.iter().map(ToString::to_string).collect::<Vec<_>>();
+        messages.sort();
+        assert_eq!(
+            messages,
+            vec![
+                "the default at #/properties/count/default doesn't conform to its schema: -1 did \
+                not conform to the schema at $: the value -1 < the minimum 0",
+                "the schema documents the default for count as -1, but serde produces 0 when \
+                it's missing",
+                "the schema documents the default for legs as 4, but serde produces 2 when \
+                it's missing",
+            ],
+        );
+        assert!(matches!(errors[0], Error::InvalidDefault { .. }));
+    }
+}
@@ -0,0 +1,293 @@
+use schemars::schema::{RootSchema, Schema};
+use serde_json::Value;
+
+use crate::{escape_pointer_segment, validate_value, Error, ErrorKind, Options};
+
+/// Shrink a value that fails validation to the smallest one we can find that
+/// fails in the same way: with an error of the same kind, for the same
+/// keyword at the same location (ignoring array indices).
+///
+/// We repeatedly remove properties and array items and simplify scalars,
+/// keeping each change only if the error still reproduces and no new errors
+/// appear, so required properties remain. If the error doesn't reproduce in
+/// the first place, the value is returned unchanged.
+pub fn minimize(schema: &RootSchema, value: &Value, error: &Error, options: &Options) -> Value {
+    let Some(target) = Signature::of(error) else {
+        return value.clone();
+    };
+    let known = validate_value(schema, value, options)
+        .errors
+        .iter()
+        .filter_map(Signature::of)
+        .collect::<Vec<_>>();
+    if !known.contains(&target) {
+        return value.clone();
+    }
+
+    // A scalar that fails is the evidence of the error, so we leave it be.
+    let failing_scalar = match error {
+        Error::InvalidValue { value, .. } if !value.is_array() && !value.is_object() => {
+            Some(value.clone())
+        }
+        _ => None,
+    };
+
+    let mut shrinker = Shrinker {
+        schema,
+        options,
+        target,
+        failing_scalar,
+        known,
+        current: value.clone(),
+    };
+
+    // Simplifying one part of the value can allow another to be removed, so
+    // keep going until we can't do any better.
+    loop {
+        let before = shrinker.current.clone();
+        shrinker.shrink(String::new());
+        if shrinker.current == before {
+            break;
+        }
+    }
+    shrinker.current
+}
+
+/// What makes one error the same as another for our purposes.
+#[derive(PartialEq)]
+struct Signature {
+    kind: ErrorKind,
+    location: String,
+    schema: Schema,
+}
+
+impl Signature {
+    fn of(error: &Error) -> Option<Self> {
+        match error {
+            Error::InvalidValue {
+                kind, path, schema, ..
+            } => Some(Self {
+                kind: *kind,
+                location: without_indices(path),
+                schema: schema.as_ref().clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Replace array indices in a path, which change as items are removed.
+fn without_indices(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    let mut in_index = false;
+    for c in path.chars() {
+        match c {
+            '[' => {
+                in_index = true;
+                out.push_str("[]");
+            }
+            ']' if in_index => in_index = false,
+            _ if in_index => (),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+struct Shrinker<'a> {
+    schema: &'a RootSchema,
+    options: &'a Options,
+    target: Signature,
+    failing_scalar: Option<Value>,
+    /// The errors for the original value; we don't accept changes that
+    /// introduce others.
+    known: Vec<Signature>,
+    current: Value,
+}
+
+impl Shrinker<'_> {
+    fn reproduces(&self, value: &Value) -> bool {
+        let errors = validate_value(self.schema, value, self.options).errors;
+        let mut found = false;
+        for error in &errors {
+            let Some(signature) = Signature::of(error) else {
+                return false;
+            };
+            if !self.known.contains(&signature) {
+                return false;
+            }
+            if signature == self.target {
+                found |= match (&self.failing_scalar, error) {
+                    (Some(scalar), Error::InvalidValue { value, .. }) => value == scalar,
+                    _ => true,
+                };
+            }
+        }
+        found
+    }
+
+    /// Apply a change to the node at the given pointer, keeping it if the
+    /// error still reproduces.
+    fn attempt(&mut self, pointer: &str, change: impl FnOnce(&mut Value)) -> bool {
+        let mut candidate = self.current.clone();
+        let Some(node) = candidate.pointer_mut(pointer) else {
+            return false;
+        };
+        change(node);
+        if self.reproduces(&candidate) {
+            self.current = candidate;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn shrink(&mut self, pointer: String) {
+        let Some(node) = self.current.pointer(&pointer) else {
+            return;
+        };
+
+        match node {
+            Value::Array(items) => {
+                let len = items.len();
+                self.remove_chunks(&pointer, len, |node, start, end| {
+                    if let Value::Array(items) = node {
+                        items.drain(start..end);
+                    }
+                });
+                let len = match self.current.pointer(&pointer) {
+                    Some(Value::Array(items)) => items.len(),
+                    _ => 0,
+                };
+                for i in 0..len {
+                    self.shrink(format!("{}/{}", pointer, i));
+                }
+            }
+            Value::Object(map) => {
+                let len = map.len();
+                self.remove_chunks(&pointer, len, |node, start, end| {
+                    if let Value::Object(map) = node {
+                        let keys = map.keys().skip(start).take(end - start).cloned();
+                        for key in keys.collect::<Vec<_>>() {
+                            map.remove(&key);
+                        }
+                    }
+                });
+                let keys = match self.current.pointer(&pointer) {
+                    Some(Value::Object(map)) => map.keys().cloned().collect(),
+                    _ => Vec::new(),
+                };
+                for key in keys {
+                    self.shrink(format!("{}/{}", pointer, escape_pointer_segment(&key)));
+                }
+            }
+            Value::String(s) if !s.is_empty() => {
+                // Try the empty string, then successively shorter prefixes.
+                let mut len = s.chars().count();
+                if !self.attempt(&pointer, |node| *node = Value::String(String::new())) {
+                    while len > 1 {
+                        len /= 2;
+                        let shorter = |node: &mut Value| {
+                            if let Value::String(s) = node {
+                                *s = s.chars().take(len).collect();
+                            }
+                        };
+                        if !self.attempt(&pointer, shorter) {
+                            break;
+                        }
+                    }
+                }
+            }
+            Value::Number(n) if n.as_f64() != Some(0.0) => {
+                self.attempt(&pointer, |node| *node = Value::from(0));
+            }
+            Value::Bool(true) => {
+                self.attempt(&pointer, |node| *node = Value::Bool(false));
+            }
+            _ => (),
+        }
+    }
+
+    /// Remove ever smaller runs of a container's elements.
+    fn remove_chunks(
+        &mut self,
+        pointer: &str,
+        mut len: usize,
+        remove: impl Fn(&mut Value, usize, usize),
+    ) {
+        let mut chunk = len;
+        while chunk > 0 {
+            let mut start = 0;
+            while start < len {
+                let end = (start + chunk).min(len);
+                if self.attempt(pointer, |node| remove(node, start, end)) {
+                    len -= end - start;
+                } else {
+                    start = end;
+                }
+            }
+            chunk /= 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::{schema_for, JsonSchema};
+    use serde::Serialize;
+    use serde_json::json;
+
+    use crate::{minimize, validate, Options};
+
+    #[derive(Serialize, JsonSchema)]
+    struct Cluster {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        region: Option<String>,
+        nodes: Vec<Node>,
+    }
+
+    #[derive(Serialize, JsonSchema)]
+    struct Node {
+        hostname: String,
+        cores: u32,
+        role: Role,
+    }
+
+    #[derive(Serialize, JsonSchema)]
+    #[allow(dead_code)]
+    enum Role {
+        #[serde(rename = "leader")]
+        Leader,
+        #[schemars(rename = "follower")]
+        Follower,
+    }
+
+    #[test]
+    fn test_minimize() {
+        let mut nodes = (0..30)
+            .map(|i| Node {
+                hostname: format!("node-{}.example.com", i),
+                cores: 16,
+                role: Role::Leader,
+            })
+            .collect::<Vec<_>>();
+        nodes[17].role = Role::Follower;
+        let item = Cluster {
+            name: "production".to_string(),
+            region: Some("us-east".to_string()),
+            nodes,
+        };
+
+        let err = validate(&item).unwrap_err();
+        let value = serde_json::to_value(&item).unwrap();
+        let minimal = minimize(&schema_for!(Cluster), &value, &err, &Options::default());
+        assert_eq!(
+            minimal,
+            json!({
+                "name": "",
+                "nodes": [{ "cores": 0, "hostname": "", "role": "Follower" }],
+            }),
+        );
+    }
+}
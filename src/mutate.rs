@@ -0,0 +1,457 @@
+use std::collections::BTreeSet;
+
+use schemars::{
+    schema::{RootSchema, Schema, SchemaObject, SingleOrVec},
+    schema_for, JsonSchema,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    escape_pointer_segment, metadata::conform, schema::reference_name, serialize, validate_value,
+    Error, Options,
+};
+
+/// The name of the property added to objects to see whether unknown
+/// properties are permitted.
+const UNKNOWN_PROPERTY: &str = "does_it_json_unknown";
+
+/// Confirm that an item matches its schema, and that the schema and
+/// `Deserialize` agree about values near it.
+///
+/// Starting from the item's serialization, this makes small changes, one at
+/// a time: removing each property, setting each to `null`, adding an unknown
+/// property, replacing each enum variant or tag with the others, and pushing
+/// numbers past their `minimum` or `maximum`. Each changed value is both
+/// validated against the schema and deserialized into `T`; if only one of
+/// the two accepts it, that's reported ([`Error::MutantDisagreement`]). This
+/// finds mismatches that [`validate`](crate::validate) can't, such as
+/// `deny_unknown_fields` without `additionalProperties: false`. Returns every
+/// disagreement found.
+pub fn validate_mutants<T: JsonSchema + Serialize + DeserializeOwned>(
+    item: &T,
+) -> Result<(), Vec<Error>> {
+    let root = schema_for!(T);
+    let value = serialize::to_value(item, &root).map_err(|e| vec![e])?;
+    let options = Options::default();
+    let outcome = validate_value(&root, &value, &options);
+    if !outcome.errors.is_empty() {
+        return Err(outcome.errors);
+    }
+
+    let mut mutants = Vec::new();
+    let schema = Schema::Object(root.schema.clone());
+    let schemas = applicable(&root, &schema, &value);
+    mutate(&root, &schemas, &value, String::new(), &mut mutants);
+
+    let errors = mutants
+        .into_iter()
+        .filter_map(|mutant| {
+            let mut whole = value.clone();
+            *whole.pointer_mut(&mutant.pointer)? = mutant.value;
+
+            let schema_error = validate_value(&root, &whole, &options)
+                .errors
+                .into_iter()
+                .next();
+            let serde_error = serde_json::from_value::<T>(whole).err();
+            let details = match (schema_error, serde_error) {
+                (None, Some(e)) => format!(
+                    "the schema accepts the result, but it doesn't deserialize: {}",
+                    e
+                ),
+                (Some(e), None) => {
+                    format!("the result deserializes, but the schema rejects it: {}", e)
+                }
+                _ => return None,
+            };
+            Some(Error::MutantDisagreement {
+                pointer: mutant.pointer,
+                mutation: mutant.mutation,
+                details,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+/// A change to the value at a pointer.
+struct Mutant {
+    pointer: String,
+    mutation: String,
+    value: Value,
+}
+
+/// The schema objects that apply to a value: the schema itself, those it
+/// refers to, those in its `allOf`, and the `anyOf` and `oneOf` branches that
+/// the value matches.
+fn applicable<'a>(
+    root: &'a RootSchema,
+    schema: &'a Schema,
+    value: &Value,
+) -> Vec<&'a SchemaObject> {
+    let Schema::Object(obj) = schema else {
+        return Vec::new();
+    };
+    let mut out = vec![obj];
+    if let Some(definition) = obj
+        .reference
+        .as_ref()
+        .and_then(|reference| root.definitions.get(reference_name(reference)))
+    {
+        out.extend(applicable(root, definition, value));
+    }
+    if let Some(subschemas) = &obj.subschemas {
+        for sub in subschemas.all_of.iter().flatten() {
+            out.extend(applicable(root, sub, value));
+        }
+        for branch in [&subschemas.any_of, &subschemas.one_of]
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            if let Schema::Object(branch_obj) = branch {
+                if conform(root, branch_obj, value).is_empty() {
+                    out.extend(applicable(root, branch, value));
+                }
+            }
+        }
+    }
+    out
+}
+
+fn mutate(
+    root: &RootSchema,
+    schemas: &[&SchemaObject],
+    value: &Value,
+    pointer: String,
+    out: &mut Vec<Mutant>,
+) {
+    let mut push = |mutation: String, value: Value| {
+        out.push(Mutant {
+            pointer: pointer.clone(),
+            mutation,
+            value,
+        })
+    };
+
+    for (mutation, replacement) in variant_swaps(root, schemas, value) {
+        push(mutation, replacement);
+    }
+
+    match value {
+        Value::Object(map) => {
+            let required = |prop: &str| {
+                schemas
+                    .iter()
+                    .filter_map(|s| s.object.as_ref())
+                    .any(|object| object.required.contains(prop))
+            };
+            for (prop, prop_value) in map {
+                let mut without = map.clone();
+                without.remove(prop);
+                let kind = if required(prop) {
+                    "required"
+                } else {
+                    "optional"
+                };
+                push(
+                    format!("removing the {} property {}", kind, prop),
+                    Value::Object(without),
+                );
+                if !prop_value.is_null() {
+                    let mut nulled = map.clone();
+                    nulled.insert(prop.clone(), Value::Null);
+                    push(format!("setting {} to null", prop), Value::Object(nulled));
+                }
+            }
+            if !map.contains_key(UNKNOWN_PROPERTY) {
+                let mut extra = map.clone();
+                extra.insert(UNKNOWN_PROPERTY.to_string(), Value::Null);
+                push(
+                    format!("adding the unknown property {}", UNKNOWN_PROPERTY),
+                    Value::Object(extra),
+                );
+            }
+        }
+        Value::Number(number) => {
+            for number_validation in schemas.iter().filter_map(|s| s.number.as_ref()) {
+                let past = [
+                    number_validation
+                        .maximum
+                        .map(|max| (max + 1.0, "above the maximum", max)),
+                    number_validation
+                        .exclusive_maximum
+                        .map(|max| (max, "at the exclusive maximum", max)),
+                    number_validation
+                        .minimum
+                        .map(|min| (min - 1.0, "below the minimum", min)),
+                    number_validation
+                        .exclusive_minimum
+                        .map(|min| (min, "at the exclusive minimum", min)),
+                ];
+                for (replacement, relation, bound) in past.into_iter().flatten() {
+                    let replacement = number_like(number, replacement);
+                    push(
+                        format!(
+                            "setting the value to {}, {} {}",
+                            replacement, relation, bound
+                        ),
+                        replacement,
+                    );
+                }
+            }
+        }
+        _ => (),
+    }
+
+    // Then the values within this one.
+    match value {
+        Value::Object(map) => {
+            for (prop, prop_value) in map {
+                let child = schemas
+                    .iter()
+                    .filter_map(|s| property_schema(s, prop))
+                    .flat_map(|s| applicable(root, s, prop_value))
+                    .collect::<Vec<_>>();
+                mutate(
+                    root,
+                    &child,
+                    prop_value,
+                    format!("{}/{}", pointer, escape_pointer_segment(prop)),
+                    out,
+                );
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                let child = schemas
+                    .iter()
+                    .filter_map(|s| match s.array.as_ref()?.items.as_ref()? {
+                        SingleOrVec::Single(items) => Some(items.as_ref()),
+                        SingleOrVec::Vec(items) => items.get(i),
+                    })
+                    .flat_map(|s| applicable(root, s, item))
+                    .collect::<Vec<_>>();
+                mutate(root, &child, item, format!("{}/{}", pointer, i), out);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// The schema for a property of an object.
+fn property_schema<'a>(schema: &'a SchemaObject, prop: &str) -> Option<&'a Schema> {
+    let object = schema.object.as_ref()?;
+    object
+        .properties
+        .get(prop)
+        .or_else(|| {
+            object
+                .pattern_properties
+                .iter()
+                .find(|(pattern, _)| {
+                    regress::Regex::new(pattern).is_ok_and(|regex| regex.find(prop).is_some())
+                })
+                .map(|(_, schema)| schema)
+        })
+        .or(object.additional_properties.as_deref())
+}
+
+/// Replace the enum variant that the value represents with each of the
+/// others: a string for a unit variant, the single property of an
+/// externally tagged variant, or the tag property of an internally or
+/// adjacently tagged one.
+fn variant_swaps(
+    root: &RootSchema,
+    schemas: &[&SchemaObject],
+    value: &Value,
+) -> Vec<(String, Value)> {
+    let branches = schemas
+        .iter()
+        .filter_map(|s| s.subschemas.as_ref())
+        .flat_map(|subschemas| [&subschemas.one_of, &subschemas.any_of])
+        .flatten()
+        .flatten()
+        .filter_map(|branch| resolve(root, branch))
+        .chain(schemas.iter().copied())
+        .collect::<Vec<_>>();
+
+    let mut swaps = Vec::new();
+    match value {
+        Value::String(current) => {
+            for name in distinct(branches.iter().flat_map(|b| strings(b)), current) {
+                swaps.push((
+                    format!("replacing the variant {} with {}", current, name),
+                    Value::String(name),
+                ));
+            }
+        }
+        Value::Object(map) => {
+            if let (1, Some((current, inner))) = (map.len(), map.iter().next()) {
+                for name in distinct(branches.iter().filter_map(|b| external_name(b)), current) {
+                    swaps.push((
+                        format!("replacing the variant {} with {}", current, name),
+                        Value::Object(Map::from_iter([(name, inner.clone())])),
+                    ));
+                }
+            }
+
+            for (tag, current) in map {
+                let Value::String(current) = current else {
+                    continue;
+                };
+                let names = branches
+                    .iter()
+                    .filter_map(|b| b.object.as_ref()?.properties.get(tag))
+                    .filter_map(|prop| resolve(root, prop))
+                    .flat_map(strings);
+                for name in distinct(names, current) {
+                    let mut swapped = map.clone();
+                    swapped.insert(tag.clone(), Value::String(name.clone()));
+                    swaps.push((
+                        format!("replacing the {} tag {} with {}", tag, current, name),
+                        Value::Object(swapped),
+                    ));
+                }
+            }
+        }
+        _ => (),
+    }
+
+    swaps
+}
+
+/// The names other than the current one, in order and without repeats.
+fn distinct(names: impl Iterator<Item = String>, current: &str) -> BTreeSet<String> {
+    names.filter(|name| name != current).collect()
+}
+
+fn resolve<'a>(root: &'a RootSchema, schema: &'a Schema) -> Option<&'a SchemaObject> {
+    match schema {
+        Schema::Object(SchemaObject {
+            reference: Some(reference),
+            ..
+        }) => resolve(root, root.definitions.get(reference_name(reference))?),
+        Schema::Object(obj) => Some(obj),
+        Schema::Bool(_) => None,
+    }
+}
+
+/// The strings that a schema's `const` or `enum` permits.
+fn strings(schema: &SchemaObject) -> Vec<String> {
+    schema
+        .const_value
+        .iter()
+        .chain(schema.enum_values.iter().flatten())
+        .filter_map(|v| v.as_str().map(String::from))
+        .collect()
+}
+
+/// The name of an externally tagged variant with data: an object with a
+/// single required property and no others.
+fn external_name(schema: &SchemaObject) -> Option<String> {
+    let object = schema.object.as_ref()?;
+    match (
+        object.required.len(),
+        object.properties.len(),
+        object.additional_properties.as_deref(),
+    ) {
+        (1, 1, Some(Schema::Bool(false))) => object.required.iter().next().cloned(),
+        _ => None,
+    }
+}
+
+/// A number of the same kind as the original: an integer if it was one.
+fn number_like(original: &serde_json::Number, value: f64) -> Value {
+    if original.is_f64() || value.fract() != 0.0 {
+        Value::from(value)
+    } else if value < 0.0 {
+        Value::from(value as i64)
+    } else {
+        Value::from(value as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+
+    use crate::{validate_mutants, Error};
+
+    #[test]
+    fn test_mutants() {
+        #[derive(Serialize, Deserialize, JsonSchema)]
+        #[serde(deny_unknown_fields)]
+        struct Agreeable {
+            #[schemars(range(max = 10))]
+            #[serde(deserialize_with = "at_most_ten")]
+            level: u32,
+            name: Option<String>,
+            mode: Mode,
+        }
+
+        fn at_most_ten<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+            let level = u32::deserialize(deserializer)?;
+            match level {
+                0..=10 => Ok(level),
+                _ => Err(serde::de::Error::custom("level must be at most 10")),
+            }
+        }
+
+        #[derive(Serialize, Deserialize, JsonSchema)]
+        enum Mode {
+            Fast,
+            Slow,
+        }
+
+        assert!(validate_mutants(&Agreeable {
+            level: 3,
+            name: None,
+            mode: Mode::Fast,
+        })
+        .is_ok());
+
+        #[derive(Serialize, Deserialize, JsonSchema)]
+        #[schemars(deny_unknown_fields)]
+        struct Config {
+            #[schemars(range(max = 10))]
+            level: u32,
+            mode: Mode,
+        }
+
+        let errors = validate_mutants(&Config {
+            level: 3,
+            mode: Mode::Fast,
+        })
+        .unwrap_err();
+        let found = errors
+            .iter()
+            .map(|error| match error {
+                Error::MutantDisagreement {
+                    pointer,
+                    mutation,
+                    details,
+                } => {
+                    assert!(
+                        details.starts_with("the result deserializes, but the schema rejects it")
+                    );
+                    (pointer.as_str(), mutation.as_str())
+                }
+                other => panic!("unexpected error {:?}", other),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                ("", "adding the unknown property does_it_json_unknown"),
+                ("/level", "setting the value to 11, above the maximum 10"),
+            ],
+        );
+    }
+}
@@ -0,0 +1,577 @@
+use std::fmt;
+
+use serde::{
+    ser::{
+        self, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+        SerializeTupleStruct, SerializeTupleVariant,
+    },
+    Serialize,
+};
+use serde_json::{Map, Value};
+
+use crate::Error;
+
+/// Marks a string that stands in for a non-finite float until `check` finds
+/// it; serde_json itself writes such floats as `null`.
+const NON_FINITE: &str = "\u{0}does-it-json non-finite float ";
+
+/// Why serializing with [`Serializer`] failed.
+#[derive(Debug)]
+pub enum Failure {
+    /// serde_json, or the item's own `Serialize`, rejected the item.
+    Json(serde_json::Error),
+    /// An integer exceeds 64 bits.
+    Wide(String),
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Json(err) => err.fmt(f),
+            Failure::Wide(value) => write!(f, "{} exceeds 64 bits", value),
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+impl ser::Error for Failure {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Failure::Json(<serde_json::Error as ser::Error>::custom(msg))
+    }
+}
+
+impl From<serde_json::Error> for Failure {
+    fn from(err: serde_json::Error) -> Self {
+        Failure::Json(err)
+    }
+}
+
+/// Serializes to the same value as `serde_json::value::Serializer`, except
+/// that integers beyond 64 bits fail with [`Failure::Wide`] and non-finite
+/// floats are marked so that [`check`] can report them.
+pub struct Serializer;
+
+/// Find the numbers in a value from [`Serializer`] that serde_json can't
+/// faithfully write: non-finite floats, which are replaced with the `null`
+/// serde_json writes, and, if requested, floats that don't read back as the
+/// same value.
+pub fn check(value: &mut Value, round_trip: bool) -> Vec<Error> {
+    let mut errors = Vec::new();
+    check_at("$".to_string(), value, round_trip, &mut errors);
+    errors
+}
+
+fn check_at(path: String, value: &mut Value, round_trip: bool, errors: &mut Vec<Error>) {
+    match value {
+        Value::String(s) => {
+            if let Some(float) = s.strip_prefix(NON_FINITE) {
+                errors.push(Error::NonFiniteFloat {
+                    path,
+                    value: float.parse().unwrap_or(f64::NAN),
+                });
+                *value = Value::Null;
+            }
+        }
+        Value::Number(n) if round_trip && n.is_f64() => {
+            let written = n.as_f64().unwrap_or_default();
+            let read = serde_json::from_str::<f64>(&n.to_string()).ok();
+            if let Some(read) = read.filter(|read| *read != written) {
+                errors.push(Error::FloatRoundTrip {
+                    path,
+                    value: format!("{:?}", written),
+                    read: format!("{:?}", read),
+                });
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                check_at(format!("{}[{}]", path, i), item, round_trip, errors);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                check_at(format!("{}.{}", path, key), item, round_trip, errors);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn float(value: f64) -> Value {
+    if value.is_finite() {
+        Value::from(value)
+    } else {
+        Value::String(format!("{}{}", NON_FINITE, value))
+    }
+}
+
+/// Render a map key as serde_json would, or fail as it would.
+fn map_key<T: ?Sized + Serialize>(key: &T) -> Result<String, Failure> {
+    let mut map = ser::Serializer::serialize_map(serde_json::value::Serializer, Some(1))?;
+    map.serialize_key(key)?;
+    map.serialize_value(&())?;
+    Ok(match SerializeMap::end(map)? {
+        Value::Object(map) => map.into_iter().next().map(|(key, _)| key),
+        _ => None,
+    }
+    .unwrap_or_default())
+}
+
+/// An array or object under construction, within the named variant if any.
+pub struct Compound {
+    variant: Option<&'static str>,
+    items: Vec<Value>,
+    map: Map<String, Value>,
+    key: Option<String>,
+}
+
+impl Compound {
+    fn new(variant: Option<&'static str>) -> Self {
+        Compound {
+            variant,
+            items: Vec::new(),
+            map: Map::new(),
+            key: None,
+        }
+    }
+
+    fn wrap(variant: Option<&'static str>, value: Value) -> Value {
+        match variant {
+            Some(variant) => Value::Object([(variant.to_string(), value)].into_iter().collect()),
+            None => value,
+        }
+    }
+
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Failure> {
+        self.items.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn field<T: ?Sized + Serialize>(&mut self, key: &str, value: &T) -> Result<(), Failure> {
+        self.map
+            .insert(key.to_string(), value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn array(self) -> Result<Value, Failure> {
+        Ok(Compound::wrap(self.variant, Value::Array(self.items)))
+    }
+
+    fn object(self) -> Result<Value, Failure> {
+        Ok(Compound::wrap(self.variant, Value::Object(self.map)))
+    }
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = Value;
+    type Error = Failure;
+    type SerializeSeq = Compound;
+    type SerializeTuple = Compound;
+    type SerializeTupleStruct = Compound;
+    type SerializeTupleVariant = Compound;
+    type SerializeMap = Compound;
+    type SerializeStruct = Compound;
+    type SerializeStructVariant = Compound;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Failure> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, Failure> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, Failure> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, Failure> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, Failure> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value, Failure> {
+        if let Ok(v) = i64::try_from(v) {
+            Ok(Value::from(v))
+        } else if let Ok(v) = u64::try_from(v) {
+            Ok(Value::from(v))
+        } else {
+            Err(Failure::Wide(v.to_string()))
+        }
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, Failure> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, Failure> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, Failure> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, Failure> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value, Failure> {
+        u64::try_from(v)
+            .map(Value::from)
+            .map_err(|_| Failure::Wide(v.to_string()))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, Failure> {
+        Ok(float(f64::from(v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, Failure> {
+        Ok(float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, Failure> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, Failure> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Failure> {
+        Ok(Value::Array(v.iter().map(|&b| Value::from(b)).collect()))
+    }
+
+    fn serialize_none(self) -> Result<Value, Failure> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Value, Failure> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Failure> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Value, Failure> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Value, Failure> {
+        Ok(Value::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Value, Failure> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, Failure> {
+        Ok(Compound::wrap(Some(variant), value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Compound, Failure> {
+        Ok(Compound::new(None))
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Compound, Failure> {
+        Ok(Compound::new(None))
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Compound, Failure> {
+        Ok(Compound::new(None))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<Compound, Failure> {
+        Ok(Compound::new(Some(variant)))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Compound, Failure> {
+        Ok(Compound::new(None))
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Compound, Failure> {
+        Ok(Compound::new(None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<Compound, Failure> {
+        Ok(Compound::new(Some(variant)))
+    }
+}
+
+impl SerializeSeq for Compound {
+    type Ok = Value;
+    type Error = Failure;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Failure> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Value, Failure> {
+        self.array()
+    }
+}
+
+impl SerializeTuple for Compound {
+    type Ok = Value;
+    type Error = Failure;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Failure> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Value, Failure> {
+        self.array()
+    }
+}
+
+impl SerializeTupleStruct for Compound {
+    type Ok = Value;
+    type Error = Failure;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Failure> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Value, Failure> {
+        self.array()
+    }
+}
+
+impl SerializeTupleVariant for Compound {
+    type Ok = Value;
+    type Error = Failure;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Failure> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Value, Failure> {
+        self.array()
+    }
+}
+
+impl SerializeMap for Compound {
+    type Ok = Value;
+    type Error = Failure;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Failure> {
+        self.key = Some(map_key(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Failure> {
+        let key = self.key.take().unwrap_or_default();
+        self.field(&key, value)
+    }
+
+    fn end(self) -> Result<Value, Failure> {
+        self.object()
+    }
+}
+
+impl SerializeStruct for Compound {
+    type Ok = Value;
+    type Error = Failure;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Failure> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Value, Failure> {
+        self.object()
+    }
+}
+
+impl SerializeStructVariant for Compound {
+    type Ok = Value;
+    type Error = Failure;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Failure> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Value, Failure> {
+        self.object()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::JsonSchema;
+    use serde::Serialize;
+
+    use crate::{validate, validate_with_options, Options};
+
+    #[test]
+    fn test_non_finite() {
+        #[derive(Serialize, JsonSchema)]
+        struct Stats {
+            ratio: f64,
+            samples: Vec<f32>,
+        }
+
+        let outcome = validate_with_options(
+            &Stats {
+                ratio: f64::NAN,
+                samples: vec![1.0, f32::NEG_INFINITY],
+            },
+            &Options::default(),
+        );
+        let errors = outcome
+            .errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            &errors[..2],
+            [
+                "$.ratio is NaN, which serde_json writes as null",
+                "$.samples[1] is -infinity, which serde_json writes as null",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_non_finite_null_allowed() {
+        // The schema permits `null` in both places, so nothing but the float
+        // itself explains the `null`.
+        #[derive(Serialize, JsonSchema)]
+        #[serde(untagged)]
+        enum Level {
+            Unknown,
+            Known(f64),
+        }
+
+        #[derive(Serialize, JsonSchema)]
+        struct Reading {
+            best: Option<f64>,
+            level: Level,
+        }
+
+        let outcome = validate_with_options(
+            &Reading {
+                best: Some(f64::NAN),
+                level: Level::Known(f64::INFINITY),
+            },
+            &Options::default(),
+        );
+        let errors = outcome
+            .errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                "$.best is NaN, which serde_json writes as null",
+                "$.level is infinity, which serde_json writes as null",
+            ],
+        );
+        assert!(validate(&Reading {
+            best: None,
+            level: Level::Unknown,
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn test_wide_integers() {
+        #[derive(Serialize, JsonSchema)]
+        struct Ledger {
+            small: i128,
+            large: u128,
+        }
+
+        assert!(validate(&Ledger {
+            small: -5,
+            large: 5
+        })
+        .is_ok());
+
+        let outcome = validate_with_options(
+            &Ledger {
+                small: -5,
+                large: u128::MAX,
+            },
+            &Options::default(),
+        );
+        let errors = outcome
+            .errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                "$.large is 340282366920938463463374607431768211455, which exceeds 64 bits; \
+                serde_json can only serialize it with the `arbitrary_precision` feature"
+            ],
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        #[derive(Serialize, JsonSchema)]
+        struct Measurement {
+            value: f64,
+        }
+
+        let item = Measurement {
+            value: 1.0715660391465826e-75,
+        };
+        assert!(validate(&item).is_ok());
+
+        let options = Options {
+            float_round_trip: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            validate_with_options(&item, &options)
+                .into_result()
+                .unwrap_err()
+                .to_string(),
+            "$.value is 1.0715660391465826e-75, which reads back from JSON as \
+            1.0715660391465825e-75",
+        );
+    }
+}
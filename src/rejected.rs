@@ -0,0 +1,118 @@
+use schemars::{schema_for, JsonSchema};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{validate_value, Error, Options};
+
+/// Confirm that a value is rejected both by `T`'s schema and by deserializing
+/// into `T`.
+///
+/// If only one of the two rejects the value, the schema and `Deserialize`
+/// disagree about it, and the error says which accepted it.
+pub fn check_rejected<T: JsonSchema + DeserializeOwned>(value: &Value) -> Result<(), Error> {
+    let schema_error = validate_value(&schema_for!(T), value, &Options::default())
+        .errors
+        .into_iter()
+        .next();
+    let serde_error = T::deserialize(value).err();
+
+    let details = match (schema_error, serde_error) {
+        (Some(_), Some(_)) => return Ok(()),
+        (None, None) => "both the schema and `Deserialize` accept it".to_string(),
+        (None, Some(e)) => format!(
+            "the schema accepts it, though `Deserialize` rejects it ({}); they disagree",
+            e
+        ),
+        (Some(e), None) => format!(
+            "`Deserialize` accepts it, though the schema rejects it ({}); they disagree",
+            e
+        ),
+    };
+    Err(Error::NotRejected {
+        value: value.clone(),
+        details,
+    })
+}
+
+/// Assert that a value is rejected both by `T`'s schema and by deserializing
+/// into `T`; see [`check_rejected`].
+#[track_caller]
+pub fn assert_rejected<T: JsonSchema + DeserializeOwned>(value: &Value) {
+    if let Err(e) = check_rejected::<T>(value) {
+        panic!("{}", e);
+    }
+}
+
+/// Assert that each of a list of values is rejected both by a type's schema
+/// and by deserializing into that type, reporting every value that isn't.
+///
+/// The type comes first, followed by a colon and the values, as in
+/// `assert_all_rejected!(Point: json!({ "x": 1 }), json!([1, 2]))`; see
+/// [`check_rejected`].
+#[macro_export]
+macro_rules! assert_all_rejected {
+    ($ty:ty: $($value:expr),+ $(,)?) => {{
+        let failures = [$($value),+]
+            .iter()
+            .enumerate()
+            .filter_map(|(i, value)| {
+                $crate::check_rejected::<$ty>(value)
+                    .err()
+                    .map(|e| format!("case {}: {}", i, e))
+            })
+            .collect::<::std::vec::Vec<_>>();
+        if !failures.is_empty() {
+            panic!(
+                "{} of the values for {} weren't rejected:\n{}",
+                failures.len(),
+                stringify!($ty),
+                failures.join("\n"),
+            );
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::JsonSchema;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use crate::check_rejected;
+
+    #[derive(Deserialize, JsonSchema)]
+    #[allow(dead_code)]
+    #[serde(deny_unknown_fields)]
+    struct Config {
+        #[schemars(range(max = 10))]
+        level: u32,
+    }
+
+    #[test]
+    fn test_rejected() {
+        crate::assert_all_rejected!(Config:
+            json!({}),
+            json!({ "level": -1 }),
+            json!({ "level": 1, "extra": true }),
+        );
+
+        assert_eq!(
+            check_rejected::<Config>(&json!({ "level": 1 }))
+                .unwrap_err()
+                .to_string(),
+            r#"{"level":1} should be rejected, but both the schema and `Deserialize` accept it"#,
+        );
+        assert_eq!(
+            check_rejected::<Config>(&json!({ "level": 11 }))
+                .unwrap_err()
+                .to_string(),
+            r#"{"level":11} should be rejected, but `Deserialize` accepts it, though the schema rejects it (11 did not conform to the schema at $.level (in Config): the value 11 > the maximum 10); they disagree"#,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "1 of the values for Config weren't rejected:\ncase 1: ")]
+    fn test_all_rejected() {
+        crate::assert_all_rejected!(Config: json!({}), json!({ "level": 11 }));
+    }
+}
@@ -0,0 +1,531 @@
+use std::collections::BTreeMap;
+
+use schemars::{
+    schema::{RootSchema, Schema},
+    Map,
+};
+use serde_json::Value;
+
+use crate::{escape_pointer_segment, minimize, schema::reference_name, Error, Options};
+
+/// Lines of the value to show on either side of the one that failed.
+const CONTEXT_LINES: usize = 3;
+/// Lines of a failing object or array to show before eliding the rest.
+const MAX_FAILING_LINES: usize = 12;
+/// Array items to show before eliding the rest.
+const MAX_ITEMS: usize = 8;
+/// Characters of a string to show before eliding the rest.
+const MAX_STRING: usize = 64;
+
+/// How much of the schema and value a failure report includes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Verbosity {
+    /// The subschema that failed, with references resolved one level deep,
+    /// and an excerpt of the value around the node that failed it.
+    #[default]
+    Focused,
+    /// As [`Verbosity::Focused`], followed by the smallest value that fails
+    /// in the same way if that's smaller than the value itself; see
+    /// [`minimize`]. Finding it validates the value many times over, which
+    /// can be slow for a large value.
+    Minimized,
+    /// The entire schema and value.
+    Full,
+}
+
+/// Describe a validation failure along with the relevant parts of the schema
+/// and of the value that was validated.
+pub fn report(error: &Error, schema: &RootSchema, value: &Value, verbosity: Verbosity) -> String {
+    match (verbosity, error) {
+        (
+            Verbosity::Focused | Verbosity::Minimized,
+            Error::InvalidValue {
+                pointer,
+                schema: subschema,
+                ..
+            },
+        ) => {
+            let subschema = serde_json::to_value(subschema).unwrap();
+            let mut out = format!(
+                "error: {}\nschema: {}\nvalue:\n{}",
+                error,
+                serde_json::to_string_pretty(&inline_references(&subschema, &schema.definitions))
+                    .unwrap(),
+                excerpt(value, pointer),
+            );
+
+            if verbosity == Verbosity::Focused {
+                return out;
+            }
+            // A smaller value that fails the same way may make it easier to
+            // see what's wrong.
+            let minimal = minimize(schema, value, error, &Options::default());
+            if minimal != *value {
+                out.push_str(&format!(
+                    "minimal failing value: {}\n",
+                    serde_json::to_string_pretty(&minimal).unwrap(),
+                ));
+            }
+            out
+        }
+        // Other errors don't concern any particular part of the value.
+        (Verbosity::Focused | Verbosity::Minimized, _) => format!("error: {}", error),
+        (Verbosity::Full, _) => format!(
+            "error: {}\nschema: {}\nvalue: {}",
+            error,
+            serde_json::to_string_pretty(schema).unwrap(),
+            serde_json::to_string_pretty(value).unwrap(),
+        ),
+    }
+}
+
+/// Replace references with the definitions they refer to, but leave any
+/// references within those definitions be.
+fn inline_references(schema: &Value, definitions: &Map<String, Schema>) -> Value {
+    match schema {
+        Value::Object(map) => {
+            let definition = map
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|reference| definitions.get(reference_name(reference)));
+            match definition.map(|definition| serde_json::to_value(definition).unwrap()) {
+                Some(Value::Object(mut inlined)) => {
+                    // Keep any annotations alongside the reference.
+                    inlined.extend(
+                        map.iter()
+                            .filter(|(key, _)| *key != "$ref")
+                            .map(|(key, value)| (key.clone(), value.clone())),
+                    );
+                    Value::Object(inlined)
+                }
+                Some(inlined) => inlined,
+                None => Value::Object(
+                    map.iter()
+                        .map(|(key, value)| (key.clone(), inline_references(value, definitions)))
+                        .collect(),
+                ),
+            }
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| inline_references(item, definitions))
+                .collect(),
+        ),
+        _ => schema.clone(),
+    }
+}
+
+/// Pretty-print a value with each error placed next to the property or item
+/// it concerns, as in a compiler diagnostic. Errors that don't concern any
+/// particular part of the value follow the value.
+pub fn annotate(value: &Value, errors: &[Error]) -> String {
+    let pointers = errors.iter().filter_map(error_pointer).collect::<Vec<_>>();
+    let Printer { lines, spans, .. } = Printer::print_value(value, &pointers);
+
+    let mut annotations = BTreeMap::<usize, Vec<String>>::new();
+    let mut unplaced = Vec::new();
+    for error in errors {
+        let span = error_pointer(error).and_then(|pointer| spans.get(pointer));
+        match (error, span) {
+            (Error::InvalidValue { details, .. }, Some(span)) => annotations
+                .entry(span.first)
+                .or_default()
+                .push(details.clone()),
+            _ => unplaced.push(error.to_string()),
+        }
+    }
+
+    let mut out = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(details) = annotations.get(&i) else {
+            out.push_str(&paint_elision(line));
+            out.push('\n');
+            continue;
+        };
+        // Subsequent annotations for the same line are aligned beneath the
+        // first.
+        let indent = line.chars().count() + ANNOTATION_GAP;
+        for (j, detail) in details.iter().enumerate() {
+            if j == 0 {
+                out.push_str(&paint(line, BOLD));
+                out.push_str(&" ".repeat(ANNOTATION_GAP));
+            } else {
+                out.push_str(&" ".repeat(indent));
+            }
+            out.push_str(&paint(&format!("// ✗ {}", detail), RED));
+            out.push('\n');
+        }
+    }
+    for error in unplaced {
+        out.push_str(&paint(&format!("// ✗ {}", error), RED));
+        out.push('\n');
+    }
+    out
+}
+
+fn error_pointer(error: &Error) -> Option<&str> {
+    match error {
+        Error::InvalidValue { pointer, .. } => Some(pointer),
+        _ => None,
+    }
+}
+
+/// Spaces between a line of the value and its annotation.
+const ANNOTATION_GAP: usize = 3;
+
+const RED: &str = "31";
+const BOLD: &str = "1";
+const DIM: &str = "2";
+
+/// Apply an ANSI style to some text.
+#[cfg(feature = "color")]
+fn paint(text: &str, style: &str) -> String {
+    format!("\x1b[{}m{}\x1b[0m", style, text)
+}
+
+#[cfg(not(feature = "color"))]
+fn paint(text: &str, _style: &str) -> String {
+    text.to_string()
+}
+
+/// Dim the lines that stand in for elided array items.
+fn paint_elision(line: &str) -> String {
+    if line.trim_start().starts_with('…') {
+        paint(line, DIM)
+    } else {
+        line.to_string()
+    }
+}
+
+/// The lines of a pretty-printed value, along with the position of each
+/// node, keyed by JSON pointer.
+struct Printer<'a> {
+    /// Pointers to nodes that must not be elided.
+    keep: &'a [&'a str],
+    lines: Vec<String>,
+    spans: BTreeMap<String, Span>,
+}
+
+/// The position of a node within the printed lines.
+struct Span {
+    first: usize,
+    last: usize,
+    column: usize,
+    width: usize,
+}
+
+impl<'a> Printer<'a> {
+    fn print_value(value: &Value, keep: &'a [&'a str]) -> Self {
+        let mut printer = Printer {
+            keep,
+            lines: Vec::new(),
+            spans: BTreeMap::new(),
+        };
+        printer.print(None, value, true, 0, String::new());
+        printer
+    }
+
+    fn print(
+        &mut self,
+        key: Option<&str>,
+        value: &Value,
+        last: bool,
+        depth: usize,
+        pointer: String,
+    ) {
+        let mut prefix = "  ".repeat(depth);
+        if let Some(key) = key {
+            prefix.push_str(&format!("{}: ", Value::String(key.to_string())));
+        }
+        let suffix = if last { "" } else { "," };
+        let first = self.lines.len();
+        let column = prefix.chars().count();
+
+        let width = match value {
+            Value::Array(items) if !items.is_empty() => {
+                self.lines.push(format!("{}[", prefix));
+                let mut elided = 0;
+                for (i, item) in items.iter().enumerate() {
+                    let item_pointer = format!("{}/{}", pointer, i);
+                    if i >= MAX_ITEMS && !self.is_kept(&item_pointer) {
+                        elided += 1;
+                        continue;
+                    }
+                    self.elide(elided, depth + 1);
+                    elided = 0;
+                    self.print(None, item, i + 1 == items.len(), depth + 1, item_pointer);
+                }
+                self.elide(elided, depth + 1);
+                self.lines
+                    .push(format!("{}]{}", "  ".repeat(depth), suffix));
+                None
+            }
+            Value::Object(map) if !map.is_empty() => {
+                self.lines.push(format!("{}{{", prefix));
+                for (i, (key, item)) in map.iter().enumerate() {
+                    let item_pointer = format!("{}/{}", pointer, escape_pointer_segment(key));
+                    self.print(Some(key), item, i + 1 == map.len(), depth + 1, item_pointer);
+                }
+                self.lines
+                    .push(format!("{}}}{}", "  ".repeat(depth), suffix));
+                None
+            }
+            _ => {
+                let text = scalar(value);
+                self.lines.push(format!("{}{}{}", prefix, text, suffix));
+                Some(text.chars().count())
+            }
+        };
+
+        self.spans.insert(
+            pointer,
+            Span {
+                first,
+                last: self.lines.len() - 1,
+                column,
+                width: width.unwrap_or(1),
+            },
+        );
+    }
+
+    /// Whether the node at the given pointer is, or contains, one that must
+    /// not be elided.
+    fn is_kept(&self, pointer: &str) -> bool {
+        self.keep.iter().any(|keep| {
+            keep.strip_prefix(pointer)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+
+    fn elide(&mut self, count: usize, depth: usize) {
+        if count > 0 {
+            self.lines
+                .push(format!("{}… {} more items", "  ".repeat(depth), count));
+        }
+    }
+}
+
+/// Pretty-print a value, along with the byte range of the node at each of
+/// the given pointers.
+#[cfg(feature = "miette")]
+pub(crate) fn source_spans(
+    value: &Value,
+    pointers: &[&str],
+) -> (String, Vec<Option<std::ops::Range<usize>>>) {
+    let Printer { lines, spans, .. } = Printer::print_value(value, pointers);
+
+    let mut starts = Vec::with_capacity(lines.len());
+    let mut source = String::new();
+    for line in &lines {
+        starts.push(source.len());
+        source.push_str(line);
+        source.push('\n');
+    }
+
+    let byte_column = |line: usize, column: usize| {
+        lines[line]
+            .char_indices()
+            .nth(column)
+            .map_or(lines[line].len(), |(i, _)| i)
+    };
+
+    let ranges = pointers
+        .iter()
+        .map(|pointer| {
+            let span = spans.get(*pointer)?;
+            let start = starts[span.first] + byte_column(span.first, span.column);
+            let end = if span.first == span.last {
+                starts[span.first] + byte_column(span.first, span.column + span.width)
+            } else {
+                starts[span.last] + lines[span.last].trim_end_matches(',').len()
+            };
+            Some(start..end)
+        })
+        .collect();
+
+    (source, ranges)
+}
+
+/// The JSON for a scalar value, with long strings shortened.
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(s) if s.chars().count() > MAX_STRING => {
+            let shortened = Value::String(s.chars().take(MAX_STRING).collect()).to_string();
+            format!("{}…\"", &shortened[..shortened.len() - 1])
+        }
+        _ => value.to_string(),
+    }
+}
+
+/// Render the lines around the node at the given JSON pointer, marking the
+/// node itself.
+fn excerpt(value: &Value, pointer: &str) -> String {
+    let Printer { lines, spans, .. } = Printer::print_value(value, &[pointer]);
+
+    // If we can't find the node, show as much of the value as we would
+    // have around it.
+    let Some(&Span {
+        first,
+        last,
+        column,
+        width,
+    }) = spans.get(pointer)
+    else {
+        let shown = MAX_FAILING_LINES + 2 * CONTEXT_LINES;
+        let mut out = lines
+            .iter()
+            .take(shown)
+            .map(|line| format!("  {}\n", line))
+            .collect::<String>();
+        if lines.len() > shown {
+            out.push_str("  ⋮\n");
+        }
+        return out;
+    };
+
+    let start = first.saturating_sub(CONTEXT_LINES);
+    let end = (last + CONTEXT_LINES).min(lines.len() - 1);
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push_str("  ⋮\n");
+    }
+    for (i, line) in lines.iter().enumerate().take(end + 1).skip(start) {
+        let is_failing = (first..=last).contains(&i);
+
+        // Elide the middle of a long failing node, keeping its last line.
+        if is_failing && i - first >= MAX_FAILING_LINES && i != last {
+            if i - first == MAX_FAILING_LINES {
+                out.push_str(">   ⋮\n");
+            }
+            continue;
+        }
+
+        let gutter = if is_failing { "> " } else { "  " };
+        out.push_str(&format!("{}{}\n", gutter, line));
+        if first == last && i == first {
+            out.push_str(&format!("  {}{}\n", " ".repeat(column), "^".repeat(width)));
+        }
+    }
+    if end + 1 < lines.len() {
+        out.push_str("  ⋮\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::{schema_for, JsonSchema};
+    use serde::Serialize;
+
+    use crate::{annotate, report, validate, Verbosity};
+
+    #[derive(Serialize, JsonSchema)]
+    struct Deployment {
+        name: String,
+        replicas: Vec<Replica>,
+    }
+
+    #[derive(Serialize, JsonSchema)]
+    struct Replica {
+        id: u32,
+        note: String,
+        region: Region,
+    }
+
+    #[derive(Serialize, JsonSchema)]
+    #[allow(dead_code)]
+    enum Region {
+        #[schemars(rename = "us-east")]
+        UsEast,
+        #[serde(rename = "us-west")]
+        UsWest,
+    }
+
+    #[test]
+    fn test_focused_report() {
+        let mut replicas = (1..=20)
+            .map(|id| Replica {
+                id,
+                note: format!("replica {} ", id).repeat(10),
+                region: Region::UsWest,
+            })
+            .collect::<Vec<_>>();
+        replicas[11].region = Region::UsEast;
+        let item = Deployment {
+            name: "web".to_string(),
+            replicas,
+        };
+
+        let err = validate(&item).unwrap_err();
+        let schema = schema_for!(Deployment);
+        let value = serde_json::to_value(&item).unwrap();
+
+        let focused = report(&err, &schema, &value, Verbosity::Focused);
+        expectorate::assert_contents("tests/test_focused_report", &focused);
+
+        let minimized = report(&err, &schema, &value, Verbosity::Minimized);
+        expectorate::assert_contents(
+            "tests/test_minimized_report",
+            minimized.strip_prefix(&focused).unwrap(),
+        );
+
+        let full = report(&err, &schema, &value, Verbosity::Full);
+        assert!(full.contains(&serde_json::to_string_pretty(&schema).unwrap()));
+        assert!(full.contains(&serde_json::to_string_pretty(&value).unwrap()));
+    }
+
+    // Colour would obscure the expected output.
+    #[cfg(not(feature = "color"))]
+    #[test]
+    fn test_annotate() {
+        use crate::{validate_with_options, Options};
+
+        #[derive(Serialize, JsonSchema)]
+        struct Order {
+            id: u32,
+            lines: Vec<Line>,
+            #[schemars(rename = "shipTo")]
+            ship_to: String,
+        }
+
+        #[derive(Serialize, JsonSchema)]
+        struct Line {
+            #[schemars(with = "u32")]
+            quantity: String,
+            region: Region,
+        }
+
+        let item = Order {
+            id: 7,
+            lines: vec![
+                Line {
+                    quantity: "2".to_string(),
+                    region: Region::UsWest,
+                },
+                Line {
+                    quantity: "3".to_string(),
+                    region: Region::UsEast,
+                },
+            ],
+            ship_to: "home".to_string(),
+        };
+
+        let outcome = validate_with_options(&item, &Options::default());
+        assert_eq!(outcome.errors.len(), 4);
+        let value = serde_json::to_value(&item).unwrap();
+        expectorate::assert_contents("tests/test_annotate", &annotate(&value, &outcome.errors));
+    }
+
+    #[cfg(feature = "color")]
+    #[test]
+    fn test_annotate_color() {
+        let err = validate(&Region::UsEast).unwrap_err();
+        let value = serde_json::to_value(Region::UsEast).unwrap();
+        assert_eq!(
+            annotate(&value, &[err]),
+            "\x1b[1m\"UsEast\"\x1b[0m   \x1b[31m// ✗ not a valid enumerated value\x1b[0m\n",
+        );
+    }
+}
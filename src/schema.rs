@@ -1,38 +1,242 @@
-use std::collections::BTreeMap;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+};
 
 use regress::Regex;
-use schemars::schema::{
-    ArrayValidation, InstanceType, NumberValidation, ObjectValidation, Schema, SchemaObject,
-    SingleOrVec, StringValidation, SubschemaValidation,
+use schemars::{
+    schema::{
+        ArrayValidation, InstanceType, Metadata, NumberValidation, ObjectValidation, Schema,
+        SchemaObject, SingleOrVec, StringValidation, SubschemaValidation,
+    },
+    Map,
 };
 use serde_json::Value;
 
-use crate::Error;
+use crate::{
+    content::validate_content, enums, escape_pointer_segment, hints, Direction, Error, ErrorKind,
+    Options, Policy, PropertyOrder, ValidationOutcome, Warning,
+};
+
+/// Draft-07 keywords for which schemars has no dedicated field; these end up
+/// in `SchemaObject::extensions`. We also accept `contentSchema` from later
+/// drafts as it accompanies `contentMediaType`.
+const EXTENSION_KEYWORDS: &[&str] = &[
+    "$comment",
+    "$schema",
+    "contentEncoding",
+    "contentMediaType",
+    "contentSchema",
+    "definitions",
+    "dependencies",
+];
+
+/// Formats schemars uses to describe the Rust type of a number; these are
+/// informational, and it would be tedious to warn that they aren't validated.
+const NUMERIC_FORMATS: &[&str] = &[
+    "double", "float", "int", "int8", "int16", "int32", "int64", "int128", "uint", "uint8",
+    "uint16", "uint32", "uint64", "uint128",
+];
+
+/// State shared by a single validation pass.
+pub struct Context<'a> {
+    pub definitions: &'a Map<String, Schema>,
+    pub options: &'a Options,
+    warnings: RefCell<Vec<Warning>>,
+    /// Errors beyond the first for a given value, such as those for its
+    /// other properties, as well as errors that don't affect whether a value
+    /// matches a schema (and therefore which `oneOf` or `anyOf` branch it
+    /// matches); these are reported once validation completes.
+    deferred: RefCell<Vec<Error>>,
+    /// The names of the types whose schemas we're within.
+    types: RefCell<Vec<String>>,
+    /// The property names and array indices leading to the current value.
+    location: RefCell<Vec<String>>,
+    /// The order in which each object's properties were written, keyed by a
+    /// JSON pointer to the object; empty unless checking property order.
+    pub written: BTreeMap<String, Vec<String>>,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(definitions: &'a Map<String, Schema>, options: &'a Options) -> Self {
+        Self {
+            definitions,
+            options,
+            warnings: Default::default(),
+            deferred: Default::default(),
+            types: Default::default(),
+            location: Default::default(),
+            written: Default::default(),
+        }
+    }
+
+    pub fn finish(self, result: Result<(), Error>) -> ValidationOutcome {
+        let mut errors = result.err().into_iter().collect::<Vec<_>>();
+        errors.extend(self.deferred.into_inner());
+
+        let (promoted, warnings) = self
+            .warnings
+            .into_inner()
+            .into_iter()
+            .partition::<Vec<_>, _>(|warning| self.options.promote.contains(&warning.kind()));
+        errors.extend(promoted.into_iter().map(Error::PromotedWarning));
+
+        ValidationOutcome {
+            errors,
+            warnings,
+            value: Value::Null,
+        }
+    }
+
+    /// Record a warning, unless it's already been made for the same place,
+    /// as happens when a value is validated against several branches.
+    pub fn warn(&self, warning: Warning) {
+        let mut warnings = self.warnings.borrow_mut();
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+
+    /// Report the first of a collection of errors, deferring the rest.
+    pub fn collect(&self, errors: Vec<Error>) -> Result<(), Error> {
+        let mut errors = errors.into_iter();
+        match errors.next() {
+            Some(err) => {
+                self.deferred.borrow_mut().extend(errors);
+                Err(err)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Validate speculatively, discarding any warnings or deferred errors
+    /// produced if validation fails.
+    pub fn attempt(&self, f: impl FnOnce() -> Result<(), Error>) -> Result<(), Error> {
+        let warnings = self.warnings.borrow().len();
+        let deferred = self.deferred.borrow().len();
+        let result = f();
+        if result.is_err() {
+            self.warnings.borrow_mut().truncate(warnings);
+            self.deferred.borrow_mut().truncate(deferred);
+        }
+        result
+    }
+
+    /// Validate within the schema for the named type.
+    pub fn within<T>(&self, name: impl Into<String>, f: impl FnOnce() -> T) -> T {
+        self.types.borrow_mut().push(name.into());
+        let result = f();
+        self.types.borrow_mut().pop();
+        result
+    }
+
+    /// Validate the property or array item at the given key or index of
+    /// the current value.
+    pub fn at<T>(&self, segment: impl ToString, f: impl FnOnce() -> T) -> T {
+        self.location.borrow_mut().push(segment.to_string());
+        let result = f();
+        self.location.borrow_mut().pop();
+        result
+    }
+
+    /// A JSON pointer to the current value.
+    fn pointer(&self) -> String {
+        self.location
+            .borrow()
+            .iter()
+            .map(|segment| format!("/{}", escape_pointer_segment(segment)))
+            .collect()
+    }
+
+    pub fn invalid_value(
+        &self,
+        kind: ErrorKind,
+        path: impl Into<String>,
+        schema: Schema,
+        value: &Value,
+        details: impl Into<String>,
+    ) -> Error {
+        let hints = match &schema {
+            Schema::Object(obj) => hints::suggest(kind, obj, value),
+            Schema::Bool(_) => Vec::new(),
+        };
+        Error::InvalidValue {
+            path: path.into(),
+            pointer: self.pointer(),
+            value: value.clone(),
+            schema: Box::new(schema),
+            details: details.into(),
+            kind,
+            types: self.types.borrow().clone(),
+            hints,
+        }
+    }
+
+    /// The order in which the current object's properties were written.
+    fn written_order(&self) -> Option<&[String]> {
+        self.written.get(&self.pointer()).map(Vec::as_slice)
+    }
+
+    fn deprecated(&self, path: &str, schema: &Schema, value: &Value, details: String) {
+        match self.options.deprecated {
+            Policy::Ignore => (),
+            Policy::Warn => self.warn(Warning::Deprecated {
+                path: path.to_string(),
+                details,
+            }),
+            Policy::Error => self.deferred.borrow_mut().push(self.invalid_value(
+                ErrorKind::Deprecated,
+                path,
+                schema.clone(),
+                value,
+                details,
+            )),
+        }
+    }
+}
+
 pub fn validate_schema(
     path: &str,
     schema: &Schema,
-    definitions: &BTreeMap<String, Schema>,
+    cx: &Context,
     value: &Value,
 ) -> Result<(), Error> {
     match schema {
-        Schema::Object(obj) => validate_schema_object(path, obj, definitions, value),
+        Schema::Object(obj) => validate_schema_object(path, obj, cx, value),
         Schema::Bool(true) => Ok(()),
-        Schema::Bool(false) => Err(Error::InvalidValue {
-            path: path.to_string(),
-            value: value.clone(),
-            details: "trying to match against the empty set schema".to_string(),
-        }),
+        Schema::Bool(false) => Err(cx.invalid_value(
+            ErrorKind::False,
+            path,
+            Schema::Bool(false),
+            value,
+            "trying to match against the empty set schema",
+        )),
     }
 }
 
 pub fn validate_schema_object(
     path: &str,
     schema: &SchemaObject,
-    definitions: &BTreeMap<String, Schema>,
+    cx: &Context,
+    value: &Value,
+) -> Result<(), Error> {
+    // schemars titles the root schema (and any others so annotated) with the
+    // name of its type.
+    match schema.metadata.as_deref().and_then(|m| m.title.as_ref()) {
+        Some(title) => cx.within(title, || validate_keywords(path, schema, cx, value)),
+        None => validate_keywords(path, schema, cx, value),
+    }
+}
+
+fn validate_keywords(
+    path: &str,
+    schema: &SchemaObject,
+    cx: &Context,
     value: &Value,
 ) -> Result<(), Error> {
     let SchemaObject {
         instance_type,
+        format,
         enum_values,
         const_value,
         subschemas,
@@ -44,33 +248,48 @@ pub fn validate_schema_object(
         ..
     } = schema;
 
+    // Errors about this value get suggestions as to what might have caused
+    // them.
+    let invalid = |kind: ErrorKind, path: String, details: String| {
+        cx.invalid_value(kind, path, Schema::Object(schema.clone()), value, details)
+    };
+
     if let Some(instance_type) = instance_type {
         match instance_type {
             SingleOrVec::Single(s) => {
                 if is_valid_instance_type(s.as_ref(), value) {
                     Ok(())
                 } else {
-                    Err(Error::InvalidValue {
-                        path: path.to_string(),
-                        value: value.clone(),
-                        details: format!("value is not of type {:?}", s.as_ref()),
-                    })
+                    Err(invalid(
+                        ErrorKind::Type,
+                        path.to_string(),
+                        format!("value is not of type {:?}", s.as_ref()),
+                    ))
                 }
             }
             SingleOrVec::Vec(v) => {
                 if v.iter().any(|s| is_valid_instance_type(s, value)) {
                     Ok(())
                 } else {
-                    Err(Error::InvalidValue {
-                        path: path.to_string(),
-                        value: value.clone(),
-                        details: format!("value is not any of {:?}", v),
-                    })
+                    Err(invalid(
+                        ErrorKind::Type,
+                        path.to_string(),
+                        format!("value is not any of {:?}", v),
+                    ))
                 }
             }
         }?;
     }
 
+    if let Some(format) = format {
+        if !NUMERIC_FORMATS.contains(&format.as_str()) {
+            cx.warn(Warning::UnvalidatedFormat {
+                path: path.to_string(),
+                format: format.clone(),
+            });
+        }
+    }
+
     match (const_value, enum_values) {
         (Some(_), Some(_)) => {
             return Err(Error::InvalidSchema {
@@ -80,18 +299,18 @@ pub fn validate_schema_object(
         }
 
         (Some(const_value), None) if const_value == value => Ok(()),
-        (Some(_), None) => Err(Error::InvalidValue {
-            path: format!("{}.{}", path, "const"),
-            value: value.clone(),
-            details: "mismatch with expected const value".to_string(),
-        }),
+        (Some(_), None) => Err(invalid(
+            ErrorKind::Const,
+            format!("{}.{}", path, "const"),
+            "mismatch with expected const value".to_string(),
+        )),
 
         (None, Some(enum_values)) if enum_values.contains(value) => Ok(()),
-        (None, Some(_)) => Err(Error::InvalidValue {
-            path: format!("{}.{}", path, "enum"),
-            value: value.clone(),
-            details: "not a valid enumerated value".to_string(),
-        }),
+        (None, Some(_)) => Err(invalid(
+            ErrorKind::Enum,
+            format!("{}.{}", path, "enum"),
+            "not a valid enumerated value".to_string(),
+        )),
 
         (None, None) => Ok(()),
     }?;
@@ -110,68 +329,112 @@ pub fn validate_schema_object(
             let bad_count = set
                 .iter()
                 .filter(|sub_schema| {
-                    validate_schema(&format!("{}.allOf", path), sub_schema, definitions, value)
-                        .is_err()
+                    cx.attempt(|| {
+                        validate_schema(&format!("{}.allOf", path), sub_schema, cx, value)
+                    })
+                    .is_err()
                 })
                 .count();
             if bad_count != 0 {
-                return Err(Error::InvalidValue {
-                    path: format!("{}.allOf", path),
-                    value: value.clone(),
-                    details: format!(
+                return Err(invalid(
+                    ErrorKind::AllOf,
+                    format!("{}.allOf", path),
+                    format!(
                         "value did not validate for {} of {} `allOf` schemas",
                         bad_count,
                         set.len()
                     ),
-                });
+                ));
             }
         }
 
         if let Some(set) = any_of {
-            if !set.iter().any(|sub_schema| {
-                validate_schema(&format!("{}.anyOf", path), sub_schema, definitions, value).is_ok()
-            }) {
-                return Err(Error::InvalidValue {
-                    path: format!("{}.anyOf", path),
-                    value: value.clone(),
-                    details: "value did not validate for any `anyOf` schemas".to_string(),
+            let good_count = set
+                .iter()
+                .filter(|sub_schema| {
+                    cx.attempt(|| {
+                        validate_schema(&format!("{}.anyOf", path), sub_schema, cx, value)
+                    })
+                    .is_ok()
+                })
+                .count();
+            if good_count == 0 {
+                return Err(invalid(
+                    ErrorKind::AnyOf,
+                    format!("{}.anyOf", path),
+                    enums::diagnose(set, cx, value).unwrap_or_else(|| {
+                        "value did not validate for any `anyOf` schemas".to_string()
+                    }),
+                ));
+            }
+            if good_count > 1 {
+                cx.warn(Warning::AmbiguousAnyOf {
+                    path: path.to_string(),
+                    count: good_count,
+                    total: set.len(),
                 });
             }
         }
 
         if let Some(set) = one_of {
-            let good_count = set
+            let good = set
                 .iter()
                 .filter(|sub_schema| {
-                    validate_schema(&format!("{}.oneOf", path), sub_schema, definitions, value)
-                        .is_ok()
+                    cx.attempt(|| {
+                        validate_schema(&format!("{}.oneOf", path), sub_schema, cx, value)
+                    })
+                    .is_ok()
                 })
-                .count();
+                .collect::<Vec<_>>();
+            let good_count = good.len();
             if good_count != 1 {
-                return Err(Error::InvalidValue {
-                    path: format!("{}.oneOf", path),
-                    value: value.clone(),
-                    details: format!(
+                let generic = || {
+                    format!(
                         "value validated against {} of {} `oneOf` schemas (rather than 1)",
                         good_count,
                         set.len()
-                    ),
-                });
+                    )
+                };
+                let details = match good_count {
+                    0 => enums::diagnose(set, cx, value).unwrap_or_else(generic),
+                    _ => generic(),
+                };
+                return Err(invalid(
+                    ErrorKind::OneOf,
+                    format!("{}.oneOf", path),
+                    details,
+                ));
+            }
+
+            if is_deprecated(good[0]) {
+                cx.deprecated(
+                    path,
+                    good[0],
+                    value,
+                    match variant_name(good[0]) {
+                        Some(name) => format!("the variant {} is deprecated", name),
+                        None => "the matching `oneOf` variant is deprecated".to_string(),
+                    },
+                );
             }
         }
 
         if let Some(not_schema) = not {
-            if validate_schema(&format!("{}.not", path), not_schema, definitions, value).is_ok() {
-                return Err(Error::InvalidValue {
-                    path: format!("{}.not", path),
-                    value: value.clone(),
-                    details: "value validated `not` schemas (but must not)".to_string(),
-                });
+            if cx
+                .attempt(|| validate_schema(&format!("{}.not", path), not_schema, cx, value))
+                .is_ok()
+            {
+                return Err(invalid(
+                    ErrorKind::Not,
+                    format!("{}.not", path),
+                    "value validated `not` schemas (but must not)".to_string(),
+                ));
             }
         }
 
         let if_schema_value = if_schema.as_ref().map(|if_schema| {
-            validate_schema(&format!("{}.if", path), if_schema, definitions, value).is_ok()
+            cx.attempt(|| validate_schema(&format!("{}.if", path), if_schema, cx, value))
+                .is_ok()
         });
 
         match (if_schema_value, then_schema, else_schema) {
@@ -180,10 +443,10 @@ pub fn validate_schema_object(
                 details: "an `if` schema must have a `then` or `else`".to_string(),
             }),
             (Some(true), Some(then_schema), _) => {
-                validate_schema(&format!("{}.then", path), then_schema, definitions, value)
+                validate_schema(&format!("{}.then", path), then_schema, cx, value)
             }
             (Some(false), _, Some(else_schema)) => {
-                validate_schema(&format!("{}.else", path), else_schema, definitions, value)
+                validate_schema(&format!("{}.else", path), else_schema, cx, value)
             }
 
             (None, Some(_), None) => Err(Error::InvalidSchema {
@@ -211,63 +474,65 @@ pub fn validate_schema_object(
         exclusive_minimum,
     }) = number.as_ref().map(Box::as_ref)
     {
-        let n = value.as_f64().ok_or_else(|| Error::InvalidValue {
-            path: path.to_string(),
-            value: value.clone(),
-            details: "expected a number".to_string(),
+        let n = value.as_f64().ok_or_else(|| {
+            invalid(
+                ErrorKind::Type,
+                path.to_string(),
+                "expected a number".to_string(),
+            )
         })?;
 
         if let Some(multiple_of) = multiple_of {
             let div = n / multiple_of;
             if div - div.round() > f64::EPSILON {
-                return Err(Error::InvalidValue {
-                    path: path.to_string(),
-                    value: value.clone(),
-                    details: format!("the value {} is not a multiple of {}", n, multiple_of),
-                });
+                return Err(invalid(
+                    ErrorKind::MultipleOf,
+                    path.to_string(),
+                    format!("the value {} is not a multiple of {}", n, multiple_of),
+                ));
             }
         }
 
         if let Some(maximum) = maximum {
-            if n >= *maximum {
-                return Err(Error::InvalidValue {
-                    path: path.to_string(),
-                    value: value.clone(),
-                    details: format!("the value {} >= the maximum {}", n, maximum),
-                });
+            if n > *maximum {
+                return Err(invalid(
+                    ErrorKind::Maximum,
+                    path.to_string(),
+                    format!("the value {} > the maximum {}", n, maximum),
+                ));
             }
         }
         if let Some(exclusive_maximum) = exclusive_maximum {
-            if n > *exclusive_maximum {
-                return Err(Error::InvalidValue {
-                    path: path.to_string(),
-                    value: value.clone(),
-                    details: format!(
-                        "the value {} > the exclusive maximum {}",
+            if n >= *exclusive_maximum {
+                return Err(invalid(
+                    ErrorKind::ExclusiveMaximum,
+                    path.to_string(),
+                    format!(
+                        "the value {} >= the exclusive maximum {}",
                         n, exclusive_maximum
                     ),
-                });
+                ));
             }
         }
         if let Some(minimum) = minimum {
-            if n <= *minimum {
-                return Err(Error::InvalidValue {
-                    path: path.to_string(),
-                    value: value.clone(),
-                    details: format!("the value {} <= the minimum {}", n, minimum),
-                });
+            if n < *minimum {
+                return Err(invalid(
+                    ErrorKind::Minimum,
+                    path.to_string(),
+                    format!("the value {} < the minimum {}", n, minimum),
+                ));
             }
         }
         if let Some(exclusive_minimum) = exclusive_minimum {
-            if n < *exclusive_minimum {
-                return Err(Error::InvalidValue {
-                    path: path.to_string(),
-                    value: value.clone(),
-                    details: format!(
-                        "the value {} < the exclusive minimum {}",
+            if n <= *exclusive_minimum {
+                return Err(invalid(
+                    ErrorKind::ExclusiveMinimum,
+                    path.to_string(),
+                    format!(
+                        "the value {} <= the exclusive minimum {}",
                         n, exclusive_minimum
                     ),
-                });
+                ));
             }
         }
     }
@@ -278,41 +543,43 @@ pub fn validate_schema_object(
         pattern,
     }) = string.as_ref().map(Box::as_ref)
     {
-        let s = value.as_str().ok_or_else(|| Error::InvalidValue {
-            path: path.to_string(),
-            value: value.clone(),
-            details: "expected a string".to_string(),
+        let s = value.as_str().ok_or_else(|| {
+            invalid(
+                ErrorKind::Type,
+                path.to_string(),
+                "expected a string".to_string(),
+            )
         })?;
 
         if let Some(max_length) = max_length {
             if s.len() > *max_length as usize {
-                return Err(Error::InvalidValue {
-                    path: path.to_string(),
-                    value: value.clone(),
-                    details: format!("The string is longer than {} characters", max_length),
-                });
+                return Err(invalid(
+                    ErrorKind::MaxLength,
+                    path.to_string(),
+                    format!("The string is longer than {} characters", max_length),
+                ));
             }
         }
         if let Some(min_length) = min_length {
             if s.len() < *min_length as usize {
-                return Err(Error::InvalidValue {
-                    path: path.to_string(),
-                    value: value.clone(),
-                    details: format!("The string is shorter than {} characters", min_length),
-                });
+                return Err(invalid(
+                    ErrorKind::MinLength,
+                    path.to_string(),
+                    format!("The string is shorter than {} characters", min_length),
+                ));
             }
         }
         if let Some(pattern) = pattern {
-            let regex = Regex::new(&pattern).map_err(|_| Error::InvalidSchema {
+            let regex = Regex::new(pattern).map_err(|_| Error::InvalidSchema {
                 path: path.to_string(),
                 details: format!("{} is not a valid regex", pattern),
             })?;
             if regex.find(s).is_none() {
-                return Err(Error::InvalidValue {
-                    path: path.to_string(),
-                    value: value.clone(),
-                    details: format!("{} does not match tha pattern {}", s, pattern),
-                });
+                return Err(invalid(
+                    ErrorKind::Pattern,
+                    path.to_string(),
+                    format!("{} does not match tha pattern {}", s, pattern),
+                ));
             }
         }
     }
@@ -326,36 +593,38 @@ pub fn validate_schema_object(
         contains,
     }) = array.as_ref().map(Box::as_ref)
     {
-        let arr = value.as_array().ok_or_else(|| Error::InvalidValue {
-            path: path.to_string(),
-            value: value.clone(),
-            details: "expected an array".to_string(),
+        let arr = value.as_array().ok_or_else(|| {
+            invalid(
+                ErrorKind::Type,
+                path.to_string(),
+                "expected an array".to_string(),
+            )
         })?;
 
         let arr_count = arr.len();
 
         if let Some(max_items) = max_items {
             if arr_count > *max_items as usize {
-                return Err(Error::InvalidValue {
-                    path: path.to_string(),
-                    value: value.clone(),
-                    details: format!(
+                return Err(invalid(
+                    ErrorKind::MaxItems,
+                    path.to_string(),
+                    format!(
                         "{} items is greater that the maximum of {}",
                         arr_count, max_items
                     ),
-                });
+                ));
             }
         }
         if let Some(min_items) = min_items {
             if arr_count < *min_items as usize {
-                return Err(Error::InvalidValue {
-                    path: path.to_string(),
-                    value: value.clone(),
-                    details: format!(
+                return Err(invalid(
+                    ErrorKind::MinItems,
+                    path.to_string(),
+                    format!(
                         "{} items is less that the minimum of {}",
                         arr_count, min_items
                     ),
-                });
+                ));
             }
         }
 
@@ -367,57 +636,76 @@ pub fn validate_schema_object(
                     }
 
                     if arr[i] == arr[j] {
-                        return Err(Error::InvalidValue {
-                            path: path.to_string(),
-                            value: value.clone(),
-                            details: format!(
+                        return Err(invalid(
+                            ErrorKind::UniqueItems,
+                            path.to_string(),
+                            format!(
                                 "items should be unique, but items at [{}] and [{}] are the same",
                                 i, j,
                             ),
-                        });
+                        ));
                     }
                 }
             }
         }
 
-        match items {
-            Some(SingleOrVec::Single(item_schema)) => {
-                arr.iter().enumerate().try_for_each(|(i, item_value)| {
-                    let item_path = format!("{}[{}]", path, i);
-                    validate_schema(&item_path, item_schema, definitions, item_value)
-                })?;
-            }
-            Some(SingleOrVec::Vec(item_schemas)) => {
-                arr.iter().enumerate().zip(item_schemas).try_for_each(
-                    |((i, item_value), item_schema)| {
-                        let item_path = format!("{}[{}]", path, i);
-                        validate_schema(&item_path, item_schema, definitions, item_value)
-                    },
-                )?;
+        let validate_item = |i: usize, item_schema: &Schema, item_value: &Value| {
+            let item_path = format!("{}[{}]", path, i);
+            cx.at(i, || {
+                validate_schema(&item_path, item_schema, cx, item_value)
+            })
+            .err()
+        };
 
-                if let Some(additional_schema) = additional_items {
+        let errors = match items {
+            Some(SingleOrVec::Single(item_schema)) => {
+                let validate_items = || {
                     arr.iter()
                         .enumerate()
-                        .skip(item_schemas.len())
-                        .try_for_each(|(i, item_value)| {
-                            let item_path = format!("{}[{}]", path, i);
-                            validate_schema(&item_path, additional_schema, definitions, item_value)
-                        })?;
+                        .filter_map(|(i, item_value)| validate_item(i, item_schema, item_value))
+                        .collect()
+                };
+                // Name the collection for its items' type, if it has one.
+                match item_schema.as_ref() {
+                    Schema::Object(SchemaObject {
+                        reference: Some(reference),
+                        ..
+                    }) => cx.within(
+                        format!("Vec<{}>", reference_name(reference)),
+                        validate_items,
+                    ),
+                    _ => validate_items(),
                 }
             }
-            None => (),
-        }
+            Some(SingleOrVec::Vec(item_schemas)) => {
+                let additional_schemas = additional_items.iter().map(Box::as_ref).cycle();
+                arr.iter()
+                    .zip(item_schemas.iter().chain(additional_schemas))
+                    .enumerate()
+                    .filter_map(|(i, (item_value, item_schema))| {
+                        validate_item(i, item_schema, item_value)
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        cx.collect(errors)?;
 
         if let Some(contains_schema) = contains {
             if !arr.iter().enumerate().any(|(i, item_value)| {
                 let item_path = format!("{}[{}]", path, i);
-                validate_schema(&item_path, contains_schema, definitions, item_value).is_ok()
+                cx.attempt(|| {
+                    cx.at(i, || {
+                        validate_schema(&item_path, contains_schema, cx, item_value)
+                    })
+                })
+                .is_ok()
             }) {
-                return Err(Error::InvalidValue {
-                    path: format!("{}.contains", path),
-                    value: value.clone(),
-                    details: "array does not contain the required item".to_string(),
-                });
+                return Err(invalid(
+                    ErrorKind::Contains,
+                    format!("{}.contains", path),
+                    "array does not contain the required item".to_string(),
+                ));
             }
         }
     }
@@ -432,55 +720,73 @@ pub fn validate_schema_object(
         property_names,
     }) = object.as_ref().map(Box::as_ref)
     {
-        let map = value.as_object().ok_or_else(|| Error::InvalidValue {
-            path: path.to_string(),
-            value: value.clone(),
-            details: "expected an object".to_string(),
+        let map = value.as_object().ok_or_else(|| {
+            invalid(
+                ErrorKind::Type,
+                path.to_string(),
+                "expected an object".to_string(),
+            )
         })?;
 
         let map_count = map.iter().count();
 
         if let Some(max_properties) = max_properties {
             if map_count > *max_properties as usize {
-                return Err(Error::InvalidValue {
-                    path: path.to_string(),
-                    value: value.clone(),
-                    details: format!(
+                return Err(invalid(
+                    ErrorKind::MaxProperties,
+                    path.to_string(),
+                    format!(
                         "{} properties is greater that the maximum of {}",
                         map_count, max_properties
                     ),
-                });
+                ));
             }
         }
         if let Some(min_properties) = min_properties {
             if map_count < *min_properties as usize {
-                return Err(Error::InvalidValue {
-                    path: path.to_string(),
-                    value: value.clone(),
-                    details: format!(
+                return Err(invalid(
+                    ErrorKind::MinProperties,
+                    path.to_string(),
+                    format!(
                         "{} properties is less that the minimum of {}",
                         map_count, min_properties
                     ),
-                });
+                ));
             }
         }
 
-        for prop in required {
-            if !map.contains_key(prop) {
-                return Err(Error::InvalidValue {
-                    path: path.to_string(),
-                    value: value.clone(),
-                    details: format!("the property {} is required but absent", prop),
-                });
-            }
-        }
+        // Missing properties are reported along with any problems with
+        // those that are present.
+        let missing = required
+            .iter()
+            .filter(|prop| !map.contains_key(*prop))
+            .map(|prop| {
+                invalid(
+                    ErrorKind::Required,
+                    path.to_string(),
+                    format!("the property {} is required but absent", prop),
+                )
+            })
+            .collect::<Vec<_>>();
 
-        for (prop_name, prop_value) in map {
+        let validate_property = |prop_name: &String, prop_value: &Value| {
             let prop_path = format!("{}.{}", path, prop_name);
             let mut seen = false;
 
             if let Some(prop_schema) = properties.get(prop_name) {
-                validate_schema(&prop_path, prop_schema, definitions, prop_value)?;
+                cx.at(prop_name, || {
+                    validate_direction(&prop_path, prop_schema, cx, prop_value)?;
+                    validate_schema(&prop_path, prop_schema, cx, prop_value)?;
+                    if is_deprecated(prop_schema) {
+                        cx.deprecated(
+                            &prop_path,
+                            prop_schema,
+                            prop_value,
+                            format!("the property {} is deprecated", prop_name),
+                        );
+                    }
+                    Ok::<_, Error>(())
+                })?;
                 seen = true;
             }
 
@@ -490,41 +796,340 @@ pub fn validate_schema_object(
                     details: format!("{} is not a valid regex", pat),
                 })?;
                 if regex.find(prop_name).is_none() {
-                    validate_schema(&prop_path, pat_schema, definitions, prop_value)?;
+                    cx.at(prop_name, || {
+                        validate_schema(&prop_path, pat_schema, cx, prop_value)
+                    })?;
                     seen = true;
                 }
             }
 
-            if let (false, Some(additional_schema)) = (seen, additional_properties) {
-                validate_schema(&prop_path, additional_schema, definitions, prop_value)?;
+            match (seen, additional_properties.as_deref()) {
+                (false, Some(Schema::Bool(false))) => {
+                    return Err(invalid(
+                        ErrorKind::AdditionalProperties,
+                        format!("{}.additionalProperties", path),
+                        format!("the property {} is not permitted", prop_name),
+                    ))
+                }
+                (false, Some(additional_schema)) => {
+                    cx.at(prop_name, || {
+                        validate_schema(&prop_path, additional_schema, cx, prop_value)
+                    })?;
+                }
+                _ => (),
+            }
+
+            // A struct that doesn't deny unknown fields permits any other
+            // property, which may well be a mistake.
+            if !seen
+                && !properties.is_empty()
+                && pattern_properties.is_empty()
+                && matches!(
+                    additional_properties.as_deref(),
+                    None | Some(Schema::Bool(true))
+                )
+            {
+                cx.warn(Warning::UndeclaredProperty {
+                    path: path.to_string(),
+                    property: prop_name.clone(),
+                });
             }
 
             if let Some(property_names_schema) = property_names {
-                validate_schema(
-                    &prop_path,
-                    property_names_schema,
-                    definitions,
-                    &Value::String(prop_name.clone()),
-                )?;
+                cx.at(prop_name, || {
+                    validate_schema(
+                        &prop_path,
+                        property_names_schema,
+                        cx,
+                        &Value::String(prop_name.clone()),
+                    )
+                })?;
             }
+
+            Ok(())
+        };
+
+        cx.collect(
+            missing
+                .into_iter()
+                .chain(map.iter().filter_map(|(prop_name, prop_value)| {
+                    validate_property(prop_name, prop_value).err()
+                }))
+                .collect(),
+        )?;
+
+        if let Some(details) = property_order(properties, required, cx) {
+            cx.deferred.borrow_mut().push(invalid(
+                ErrorKind::PropertyOrder,
+                path.to_string(),
+                details,
+            ));
         }
     }
 
     if let Some(reference) = reference {
-        let idx = reference.rfind('/').ok_or_else(|| Error::InvalidSchema {
+        let ref_schema = resolve_reference(path, reference, cx)?;
+        cx.within(reference_name(reference), || {
+            validate_schema(path, ref_schema, cx, value)
+        })?;
+        if is_deprecated(ref_schema) {
+            cx.deprecated(
+                path,
+                ref_schema,
+                value,
+                format!("the type {} is deprecated", reference_name(reference)),
+            );
+        }
+    }
+
+    validate_extensions(path, schema, cx, value)?;
+
+    if cx.options.decode_content {
+        validate_content(path, schema, cx, value)?;
+    }
+
+    Ok(())
+}
+
+pub fn resolve_reference<'a>(
+    path: &str,
+    reference: &str,
+    cx: &Context<'a>,
+) -> Result<&'a Schema, Error> {
+    if !reference.contains('/') {
+        return Err(Error::InvalidSchema {
             path: path.to_string(),
             details: format!("invalid reference: {}", reference),
+        });
+    }
+    let ref_name = reference_name(reference);
+
+    cx.definitions
+        .get(ref_name)
+        .ok_or_else(|| Error::InvalidSchema {
+            path: path.to_string(),
+            details: format!("invalid reference: {}", reference),
+        })
+}
+
+pub fn reference_name(reference: &str) -> &str {
+    reference.rsplit('/').next().unwrap_or(reference)
+}
+
+fn is_deprecated(schema: &Schema) -> bool {
+    matches!(
+        schema,
+        Schema::Object(SchemaObject {
+            metadata: Some(metadata),
+            ..
+        }) if metadata.deprecated
+    )
+}
+
+/// A name for an enum variant's schema, as generated by schemars: the value
+/// of a unit variant or the tag of an externally tagged variant.
+pub fn variant_name(schema: &Schema) -> Option<String> {
+    let Schema::Object(obj) = schema else {
+        return None;
+    };
+    match (&obj.const_value, obj.enum_values.as_deref(), &obj.object) {
+        (Some(Value::String(name)), _, _) => Some(name.clone()),
+        (None, Some([Value::String(name)]), _) => Some(name.clone()),
+        (None, None, Some(object)) if object.required.len() == 1 => {
+            object.required.iter().next().cloned()
+        }
+        _ => obj.metadata.as_ref()?.title.clone(),
+    }
+}
+
+/// Describe the first of the declared properties that was written out of the
+/// order called for by the options, if any was.
+fn property_order(
+    properties: &schemars::Map<String, Schema>,
+    required: &BTreeSet<String>,
+    cx: &Context,
+) -> Option<String> {
+    let order = cx.options.property_order?;
+    let written = cx.written_order()?;
+
+    // Only the properties that this schema declares are ranked; others may
+    // belong to a flattened struct or another subschema.
+    let declared = written
+        .iter()
+        .filter_map(|prop| Some((prop, properties.keys().position(|p| p == prop)?)))
+        .collect::<Vec<_>>();
+
+    declared.windows(2).find_map(|pair| {
+        let [(before, before_index), (after, after_index)] = pair else {
+            return None;
+        };
+        let (before_required, after_required) =
+            (required.contains(*before), required.contains(*after));
+        match order {
+            PropertyOrder::Alphabetical if after < before => Some(format!(
+                "the property {} is written after {}, out of alphabetical order",
+                after, before,
+            )),
+            PropertyOrder::RequiredFirst if after_required && !before_required => Some(format!(
+                "the required property {} is written after the optional property {}",
+                after, before,
+            )),
+            PropertyOrder::Schema | PropertyOrder::RequiredFirst
+                if after_index < before_index
+                    && (order == PropertyOrder::Schema || after_required == before_required) =>
+            {
+                Some(format!(
+                    "the property {} is written after {}, but the schema declares it first",
+                    after, before,
+                ))
+            }
+            _ => None,
+        }
+    })
+}
+
+/// Check that a property may be present given the direction in which the
+/// value is traveling.
+fn validate_direction(
+    path: &str,
+    schema: &Schema,
+    cx: &Context,
+    value: &Value,
+) -> Result<(), Error> {
+    let Schema::Object(SchemaObject {
+        metadata,
+        reference,
+        ..
+    }) = schema
+    else {
+        return Ok(());
+    };
+
+    // The property's own metadata takes precedence over that of the type it
+    // refers to.
+    let metadata = metadata.as_deref().or_else(|| {
+        match resolve_reference(path, reference.as_ref()?, cx).ok()? {
+            Schema::Object(obj) => obj.metadata.as_deref(),
+            Schema::Bool(_) => None,
+        }
+    });
+
+    let Some(Metadata {
+        read_only,
+        write_only,
+        ..
+    }) = metadata
+    else {
+        return Ok(());
+    };
+
+    match cx.options.direction {
+        Some(Direction::Request) if *read_only => Err(cx.invalid_value(
+            ErrorKind::ReadOnly,
+            path,
+            schema.clone(),
+            value,
+            "the property is readOnly and must not appear in a request",
+        )),
+        Some(Direction::Response) if *write_only => Err(cx.invalid_value(
+            ErrorKind::WriteOnly,
+            path,
+            schema.clone(),
+            value,
+            "the property is writeOnly and must not appear in a response",
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn validate_extensions(
+    path: &str,
+    schema: &SchemaObject,
+    cx: &Context,
+    value: &Value,
+) -> Result<(), Error> {
+    for (keyword, keyword_value) in &schema.extensions {
+        match keyword.as_str() {
+            "dependencies" => validate_dependencies(path, schema, keyword_value, cx, value)?,
+
+            // Annotations and nested definitions don't affect validity.
+            known if EXTENSION_KEYWORDS.contains(&known) => (),
+
+            // Vendor extensions are, by convention, not validation
+            // keywords.
+            vendor if vendor.starts_with("x-") => (),
+
+            unknown => match cx.options.unknown_keywords {
+                Policy::Ignore => (),
+                Policy::Warn => cx.warn(Warning::UnknownKeyword {
+                    path: path.to_string(),
+                    keyword: unknown.to_string(),
+                }),
+                // Like other problems with the schema rather than the value,
+                // this doesn't decide which `anyOf` or `oneOf` branch the
+                // value matches.
+                Policy::Error => cx.deferred.borrow_mut().push(Error::InvalidSchema {
+                    path: path.to_string(),
+                    details: format!("unknown keyword `{}`", unknown),
+                }),
+            },
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_dependencies(
+    path: &str,
+    schema: &SchemaObject,
+    dependencies: &Value,
+    cx: &Context,
+    value: &Value,
+) -> Result<(), Error> {
+    let dependencies = dependencies
+        .as_object()
+        .ok_or_else(|| Error::InvalidSchema {
+            path: path.to_string(),
+            details: "`dependencies` must be an object".to_string(),
         })?;
-        let ref_name = &reference[idx + 1..];
 
-        let ref_schema = definitions
-            .get(ref_name)
-            .ok_or_else(|| Error::InvalidSchema {
-                path: path.to_string(),
-                details: format!("invalid reference: {}", reference),
-            })?;
+    // Like the other object keywords, `dependencies` only constrains
+    // objects.
+    let Some(map) = value.as_object() else {
+        return Ok(());
+    };
+
+    for (prop, dependency) in dependencies {
+        if !map.contains_key(prop) {
+            continue;
+        }
 
-        validate_schema(reference, ref_schema, definitions, value)?;
+        if let Value::Array(required) = dependency {
+            for dep in required {
+                let dep = dep.as_str().ok_or_else(|| Error::InvalidSchema {
+                    path: path.to_string(),
+                    details: format!("the dependencies of {} must be property names", prop),
+                })?;
+                if !map.contains_key(dep) {
+                    return Err(cx.invalid_value(
+                        ErrorKind::Dependencies,
+                        format!("{}.dependencies", path),
+                        Schema::Object(schema.clone()),
+                        value,
+                        format!("the property {} is required by {} but absent", dep, prop),
+                    ));
+                }
+            }
+        } else {
+            let dep_schema =
+                serde_json::from_value::<Schema>(dependency.clone()).map_err(|_| {
+                    Error::InvalidSchema {
+                        path: path.to_string(),
+                        details: format!("the dependency of {} must be an array or a schema", prop),
+                    }
+                })?;
+            validate_schema(&format!("{}.dependencies", path), &dep_schema, cx, value)?;
+        }
     }
 
     Ok(())
@@ -546,8 +1151,12 @@ fn is_valid_instance_type(instance_type: &InstanceType, value: &Value) -> bool {
 mod tests {
     use schemars::JsonSchema;
     use serde::Serialize;
+    use serde_json::json;
 
-    use crate::validate_with_output;
+    use crate::{
+        validate, validate_with_options, validate_with_output, Direction, Error, ErrorKind,
+        Options, Policy, PropertyOrder, ValidationOutcome, Warning, WarningKind,
+    };
 
     #[derive(Serialize, JsonSchema)]
     #[schemars(tag = "broken")]
@@ -620,4 +1229,605 @@ mod tests {
 
         validate_with_output(&item).unwrap()
     }
+
+    #[derive(Serialize)]
+    struct Span {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        start: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        end: Option<u32>,
+    }
+
+    impl JsonSchema for Span {
+        fn schema_name() -> String {
+            "Span".to_string()
+        }
+
+        fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+            let mut schema = schemars::schema::SchemaObject {
+                instance_type: Some(schemars::schema::InstanceType::Object.into()),
+                object: Some(
+                    schemars::schema::ObjectValidation {
+                        properties: [
+                            ("start".to_string(), gen.subschema_for::<u32>()),
+                            ("end".to_string(), gen.subschema_for::<u32>()),
+                        ]
+                        .into_iter()
+                        .collect(),
+                        ..Default::default()
+                    }
+                    .into(),
+                ),
+                ..Default::default()
+            };
+            schema
+                .extensions
+                .insert("dependencies".to_string(), json!({ "start": ["end"] }));
+            schema
+                .extensions
+                .insert("nullable".to_string(), json!(false));
+            schema.into()
+        }
+    }
+
+    #[test]
+    fn test_dependencies() {
+        validate(&Span {
+            start: Some(1),
+            end: Some(2),
+        })
+        .unwrap();
+        validate(&Span {
+            start: None,
+            end: Some(2),
+        })
+        .unwrap();
+
+        let err = validate(&Span {
+            start: Some(1),
+            end: None,
+        })
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"{"start":1} did not conform to the schema at $.dependencies (in Span): the property end is required by start but absent"#,
+        );
+    }
+
+    fn bounded(keyword: &str) -> schemars::schema::Schema {
+        serde_json::from_value(json!({ "type": "number", keyword: 1 })).unwrap()
+    }
+
+    #[allow(dead_code)]
+    #[derive(Serialize, JsonSchema)]
+    struct Bounds {
+        #[schemars(schema_with = "exclusive_maximum")]
+        exclusive_maximum: f64,
+        #[schemars(schema_with = "exclusive_minimum")]
+        exclusive_minimum: f64,
+        #[schemars(schema_with = "maximum")]
+        maximum: f64,
+        #[schemars(schema_with = "minimum")]
+        minimum: f64,
+    }
+
+    fn exclusive_maximum(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        bounded("exclusiveMaximum")
+    }
+
+    fn exclusive_minimum(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        bounded("exclusiveMinimum")
+    }
+
+    fn maximum(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        bounded("maximum")
+    }
+
+    fn minimum(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        bounded("minimum")
+    }
+
+    #[test]
+    fn test_bounds() {
+        // A value equal to each bound is only invalid if the bound is
+        // exclusive.
+        let mut item = Bounds {
+            exclusive_maximum: 1.0,
+            exclusive_minimum: 1.0,
+            maximum: 1.0,
+            minimum: 1.0,
+        };
+        match validate(&item) {
+            Err(crate::Error::InvalidValue { path, details, .. }) => {
+                assert_eq!(path, "$.exclusive_maximum");
+                assert_eq!(details, "the value 1 >= the exclusive maximum 1");
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        item.exclusive_maximum = 0.0;
+        match validate(&item) {
+            Err(crate::Error::InvalidValue { path, details, .. }) => {
+                assert_eq!(path, "$.exclusive_minimum");
+                assert_eq!(details, "the value 1 <= the exclusive minimum 1");
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        item.exclusive_minimum = 2.0;
+        assert!(validate(&item).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_keywords() {
+        let item = Span {
+            start: None,
+            end: None,
+        };
+
+        let warnings = validate_with_options(&item, &Options::default())
+            .into_result()
+            .unwrap();
+        assert!(warnings.is_empty());
+
+        let options = Options {
+            unknown_keywords: Policy::Warn,
+            ..Default::default()
+        };
+        let warnings = validate_with_options(&item, &options)
+            .into_result()
+            .unwrap();
+        assert_eq!(
+            warnings,
+            vec![Warning::UnknownKeyword {
+                path: "$".to_string(),
+                keyword: "nullable".to_string(),
+            }],
+        );
+
+        let options = Options {
+            unknown_keywords: Policy::Error,
+            ..Default::default()
+        };
+        let err = validate_with_options(&item, &options)
+            .into_result()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid schema at $: unknown keyword `nullable`",
+        );
+    }
+
+    fn nullable_number(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        serde_json::from_value(json!({ "type": "number", "nullable": true })).unwrap()
+    }
+
+    #[derive(Serialize, JsonSchema)]
+    #[serde(untagged)]
+    enum Amount {
+        Count(#[schemars(schema_with = "nullable_number")] u32),
+        Label(String),
+    }
+
+    #[test]
+    fn test_unknown_keyword_in_branch() {
+        let options = Options {
+            unknown_keywords: Policy::Error,
+            ..Default::default()
+        };
+
+        // The unknown keyword is reported without changing which branch
+        // matches.
+        let errors = validate_with_options(&Amount::Count(3), &options).errors;
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["invalid schema at $.anyOf: unknown keyword `nullable`"],
+        );
+
+        // Nor is it reported for a branch the value doesn't match.
+        let errors = validate_with_options(&Amount::Label("three".to_string()), &options).errors;
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        // As a warning, it's reported once for each place it applies.
+        #[derive(Serialize, JsonSchema)]
+        struct Range {
+            low: Amount,
+            high: Amount,
+        }
+
+        let options = Options {
+            unknown_keywords: Policy::Warn,
+            ..Default::default()
+        };
+        let item = Range {
+            low: Amount::Count(1),
+            high: Amount::Count(2),
+        };
+        let warnings = validate_with_options(&item, &options).warnings;
+        assert_eq!(
+            warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "$.high.anyOf: unknown keyword `nullable` was not validated",
+                "$.low.anyOf: unknown keyword `nullable` was not validated",
+            ],
+        );
+    }
+
+    fn password_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        schemars::schema::SchemaObject {
+            metadata: Some(
+                schemars::schema::Metadata {
+                    write_only: true,
+                    ..Default::default()
+                }
+                .into(),
+            ),
+            instance_type: Some(schemars::schema::InstanceType::String.into()),
+            ..Default::default()
+        }
+        .into()
+    }
+
+    #[derive(Serialize, JsonSchema)]
+    struct Account {
+        #[serde(skip_deserializing)]
+        id: u64,
+        #[schemars(schema_with = "password_schema")]
+        password: String,
+    }
+
+    #[test]
+    fn test_direction() {
+        let item = Account {
+            id: 7,
+            password: "hunter2".to_string(),
+        };
+
+        // Neither is checked unless a direction is given.
+        validate(&item).unwrap();
+
+        let options = Options {
+            direction: Some(Direction::Response),
+            ..Default::default()
+        };
+        let err = validate_with_options(&item, &options)
+            .into_result()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#""hunter2" did not conform to the schema at $.password (in Account): the property is writeOnly and must not appear in a response"#,
+        );
+
+        let options = Options {
+            direction: Some(Direction::Request),
+            ..Default::default()
+        };
+        let err = validate_with_options(&item, &options)
+            .into_result()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "7 did not conform to the schema at $.id (in Account): the property is readOnly and must not appear in a request",
+        );
+    }
+
+    #[allow(deprecated)]
+    mod deprecated {
+        use schemars::JsonSchema;
+        use serde::Serialize;
+
+        #[derive(Serialize, JsonSchema)]
+        #[deprecated]
+        pub struct Legacy {
+            pub value: u32,
+        }
+
+        #[derive(Serialize, JsonSchema)]
+        pub enum Shape {
+            Circle(u32),
+            #[deprecated]
+            Square(u32),
+        }
+
+        #[derive(Serialize, JsonSchema)]
+        pub struct Drawing {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub legacy: Option<Legacy>,
+            #[deprecated]
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub scale: Option<u32>,
+            pub shape: Shape,
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated() {
+        use deprecated::{Drawing, Legacy, Shape};
+
+        let item = Drawing {
+            legacy: None,
+            scale: None,
+            shape: Shape::Circle(1),
+        };
+        assert!(validate_with_options(&item, &Options::default())
+            .into_result()
+            .unwrap()
+            .is_empty());
+
+        let item = Drawing {
+            legacy: Some(Legacy { value: 1 }),
+            scale: Some(2),
+            shape: Shape::Square(3),
+        };
+        let warnings = validate_with_options(&item, &Options::default())
+            .into_result()
+            .unwrap();
+        assert_eq!(
+            warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "$.legacy.anyOf: the type Legacy is deprecated",
+                "$.scale: the property scale is deprecated",
+                "$.shape: the variant Square is deprecated",
+            ],
+        );
+
+        let options = Options {
+            deprecated: Policy::Error,
+            ..Default::default()
+        };
+        let err = validate_with_options(&item, &options)
+            .into_result()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"{"value":1} did not conform to the schema at $.legacy.anyOf (in Drawing): the type Legacy is deprecated"#,
+        );
+    }
+
+    fn integer_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        schemars::schema::SchemaObject {
+            instance_type: Some(schemars::schema::InstanceType::Integer.into()),
+            ..Default::default()
+        }
+        .into()
+    }
+
+    #[derive(Serialize, JsonSchema)]
+    #[serde(untagged)]
+    #[allow(dead_code)]
+    enum Count {
+        Small(u8),
+        Large(u64),
+    }
+
+    #[derive(Serialize, JsonSchema)]
+    struct Tally {
+        #[schemars(schema_with = "integer_schema")]
+        first: String,
+        #[schemars(schema_with = "integer_schema")]
+        second: String,
+        count: Count,
+        #[schemars(skip)]
+        extra: bool,
+    }
+
+    #[test]
+    fn test_outcome() {
+        let item = Tally {
+            first: "one".to_string(),
+            second: "two".to_string(),
+            count: Count::Small(3),
+            extra: true,
+        };
+
+        let ValidationOutcome {
+            errors, warnings, ..
+        } = validate_with_options(&item, &Options::default());
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                r#""one" did not conform to the schema at $.first (in Tally): value is not of type Integer"#,
+                r#""two" did not conform to the schema at $.second (in Tally): value is not of type Integer"#,
+            ],
+        );
+        assert_eq!(
+            warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "$.count: value validated against 2 of 2 `anyOf` schemas",
+                "$: the property extra is not among the declared properties",
+            ],
+        );
+
+        let options = Options {
+            promote: vec![WarningKind::UndeclaredProperty],
+            ..Default::default()
+        };
+        let outcome = validate_with_options(&item, &options);
+        assert_eq!(outcome.errors.len(), 3);
+        assert!(matches!(
+            &outcome.errors[2],
+            Error::PromotedWarning(Warning::UndeclaredProperty { property, .. })
+                if property == "extra"
+        ));
+        assert_eq!(outcome.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_all_of_outcome() {
+        #[derive(Serialize, JsonSchema)]
+        struct Ledger {
+            /// Documenting the property wraps the reference in `allOf`.
+            tally: Tally,
+        }
+
+        let item = Ledger {
+            tally: Tally {
+                first: "one".to_string(),
+                second: "two".to_string(),
+                count: Count::Small(3),
+                extra: false,
+            },
+        };
+
+        // The branch's errors are summarized rather than partially reported.
+        let errors = validate_with_options(&item, &Options::default()).errors;
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                r#"{"count":3,"extra":false,"first":"one","second":"two"} did not conform to the schema at $.tally.allOf (in Ledger): value did not validate for 1 of 1 `allOf` schemas"#,
+            ],
+        );
+    }
+    #[test]
+    fn test_types() {
+        #[derive(Serialize, JsonSchema)]
+        struct Deployment {
+            spec: Spec,
+        }
+
+        #[derive(Serialize, JsonSchema)]
+        struct Spec {
+            items: Vec<Item>,
+        }
+
+        #[derive(Serialize, JsonSchema)]
+        struct Item {
+            kind: Kind,
+        }
+
+        #[derive(Serialize, JsonSchema)]
+        enum Kind {
+            #[schemars(rename = "pod")]
+            Pod,
+        }
+
+        let item = Deployment {
+            spec: Spec {
+                items: vec![Item { kind: Kind::Pod }],
+            },
+        };
+        match validate(&item) {
+            Err(Error::InvalidValue { path, types, .. }) => {
+                assert_eq!(path, "$.spec.items[0].kind.enum");
+                assert_eq!(
+                    types,
+                    vec!["Deployment", "Spec", "Vec<Item>", "Item", "Kind"]
+                );
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_property_order() {
+        #[derive(JsonSchema)]
+        struct Entry {
+            id: u32,
+            name: String,
+            note: Option<String>,
+        }
+
+        impl Serialize for Entry {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeStruct;
+
+                let mut state = serializer.serialize_struct("Entry", 3)?;
+                state.serialize_field("note", &self.note)?;
+                state.serialize_field("name", &self.name)?;
+                state.serialize_field("id", &self.id)?;
+                state.end()
+            }
+        }
+
+        #[derive(Serialize, JsonSchema)]
+        struct Log {
+            entries: Vec<Entry>,
+        }
+
+        let item = Log {
+            entries: vec![Entry {
+                id: 1,
+                name: "first".to_string(),
+                note: None,
+            }],
+        };
+        assert!(validate(&item).is_ok());
+
+        let details = |order| {
+            let options = Options {
+                property_order: Some(order),
+                ..Default::default()
+            };
+            match validate_with_options(&item, &options).into_result() {
+                Err(Error::InvalidValue {
+                    path,
+                    kind: ErrorKind::PropertyOrder,
+                    details,
+                    ..
+                }) => format!("{}: {}", path, details),
+                other => panic!("unexpected result {:?}", other),
+            }
+        };
+        assert_eq!(
+            details(PropertyOrder::Schema),
+            "$.entries[0]: the property name is written after note, but the schema declares \
+            it first",
+        );
+        assert_eq!(
+            details(PropertyOrder::RequiredFirst),
+            "$.entries[0]: the required property name is written after the optional property \
+            note",
+        );
+        assert_eq!(
+            details(PropertyOrder::Alphabetical),
+            "$.entries[0]: the property name is written after note, out of alphabetical order",
+        );
+    }
+
+    #[test]
+    fn test_declared_order() {
+        #[derive(Serialize, JsonSchema)]
+        struct Reading {
+            value: f64,
+            unit: String,
+        }
+
+        let item = Reading {
+            value: 21.5,
+            unit: "C".to_string(),
+        };
+        let check = |order| {
+            let options = Options {
+                property_order: Some(order),
+                ..Default::default()
+            };
+            validate_with_options(&item, &options)
+                .into_result()
+                .map(|_| ())
+                .map_err(|err| err.to_string())
+        };
+
+        let alphabetical = Err(
+            "{\"unit\":\"C\",\"value\":21.5} did not conform to the schema at $ (in Reading): the \
+            property unit is written after value, out of alphabetical order"
+                .to_string(),
+        );
+        assert_eq!(check(PropertyOrder::Alphabetical), alphabetical);
+
+        // Only with schemars' `preserve_order` does the schema declare the
+        // properties in the order of the struct's fields.
+        #[cfg(feature = "preserve_order")]
+        assert_eq!(check(PropertyOrder::Schema), Ok(()));
+        #[cfg(not(feature = "preserve_order"))]
+        assert_eq!(
+            check(PropertyOrder::Schema),
+            Err(
+                "{\"unit\":\"C\",\"value\":21.5} did not conform to the schema at $ (in \
+                Reading): the property unit is written after value, but the schema declares it \
+                first"
+                    .to_string()
+            ),
+        );
+    }
 }
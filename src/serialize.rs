@@ -0,0 +1,279 @@
+use schemars::schema::{RootSchema, Schema, SchemaObject, SingleOrVec};
+use serde::Serialize;
+use serde_json::Value;
+use serde_path_to_error::{Path, Segment};
+
+use crate::{
+    numbers,
+    schema::{reference_name, variant_name},
+    Error,
+};
+
+/// Serialize an item to a value, reporting where any failure occurred.
+pub fn to_value<T: Serialize>(item: &T, schema: &RootSchema) -> Result<Value, Error> {
+    to_checked_value(item, schema, false).map(|(value, _)| value)
+}
+
+/// Serialize an item to a value as [`to_value`] does, along with errors for
+/// the numbers serde_json can't faithfully write (see [`numbers::check`]).
+pub fn to_checked_value<T: Serialize>(
+    item: &T,
+    schema: &RootSchema,
+    round_trip: bool,
+) -> Result<(Value, Vec<Error>), Error> {
+    let mut value = serde_path_to_error::serialize(item, numbers::Serializer).map_err(|e| {
+        let path = e.path().clone();
+        match e.into_inner() {
+            numbers::Failure::Json(source) => located(path, source, schema),
+            numbers::Failure::Wide(value) => Error::IntegerOutOfRange {
+                path: display_path(&path),
+                value,
+            },
+        }
+    })?;
+    let errors = numbers::check(&mut value, round_trip);
+    Ok((value, errors))
+}
+
+/// Serialize an item to bytes, reporting where any failure occurred.
+pub fn to_vec<T: Serialize>(item: &T, schema: &RootSchema) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    serde_path_to_error::serialize(item, &mut serde_json::Serializer::new(&mut bytes)).map_err(
+        |e| {
+            let path = e.path().clone();
+            located(path, e.into_inner(), schema)
+        },
+    )?;
+    Ok(bytes)
+}
+
+fn located(path: Path, source: serde_json::Error, schema: &RootSchema) -> Error {
+    let expectation = expectation(schema, &path, &source);
+    Error::SerializationError {
+        path: Some(display_path(&path)),
+        source,
+        expectation,
+    }
+}
+
+/// Render a path in the same style as validation errors.
+fn display_path(path: &Path) -> String {
+    let mut out = "$".to_string();
+    for segment in path {
+        match segment {
+            Segment::Seq { index } => out.push_str(&format!("[{}]", index)),
+            Segment::Map { key } => out.push_str(&format!(".{}", key)),
+            Segment::Enum { variant } => out.push_str(&format!(".{}", variant)),
+            Segment::Unknown => out.push_str(".?"),
+        }
+    }
+    out
+}
+
+/// Describe what the schema expects where serialization failed.
+fn expectation(schema: &RootSchema, path: &Path, error: &serde_json::Error) -> Option<String> {
+    let mut current = &schema.schema;
+    for segment in path {
+        current = alternatives(schema, current)
+            .into_iter()
+            .find_map(|obj| child(obj, segment))
+            .and_then(|child| match child {
+                Schema::Object(obj) => Some(obj),
+                Schema::Bool(_) => None,
+            })
+            .or(match segment {
+                // A unit or internally tagged variant doesn't introduce a
+                // level in the serialized value.
+                Segment::Enum { .. } => Some(current),
+                _ => None,
+            })?;
+    }
+
+    // serde_json only accepts map keys that it can render as strings.
+    if error.to_string().starts_with("key must be") {
+        let keys = alternatives(schema, current)
+            .into_iter()
+            .find_map(|obj| obj.object.as_ref()?.property_names.as_deref())
+            .map_or_else(|| "a string".to_string(), |names| describe(schema, names));
+        return Some(format!(
+            "schema expects `propertyNames` {} here; serde_json can only serialize map keys \
+            that are strings, numbers, booleans, or unit variants",
+            keys,
+        ));
+    }
+
+    Some(format!(
+        "schema expects {} here",
+        describe(schema, &Schema::Object(current.clone()))
+    ))
+}
+
+/// The schema itself along with those it refers to or combines.
+fn alternatives<'a>(root: &'a RootSchema, schema: &'a SchemaObject) -> Vec<&'a SchemaObject> {
+    let mut out = vec![schema];
+    if let Some(Schema::Object(referenced)) = schema
+        .reference
+        .as_ref()
+        .and_then(|reference| root.definitions.get(reference_name(reference)))
+    {
+        out.extend(alternatives(root, referenced));
+    }
+    if let Some(subschemas) = &schema.subschemas {
+        for sub in [&subschemas.all_of, &subschemas.any_of, &subschemas.one_of]
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            if let Schema::Object(sub) = sub {
+                out.extend(alternatives(root, sub));
+            }
+        }
+    }
+    out
+}
+
+/// The schema for the part of a value reached by following a path segment.
+fn child<'a>(schema: &'a SchemaObject, segment: &Segment) -> Option<&'a Schema> {
+    match segment {
+        Segment::Map { key } | Segment::Enum { variant: key } => {
+            let object = schema.object.as_ref()?;
+            object.properties.get(key).or(match segment {
+                Segment::Map { .. } => object.additional_properties.as_deref(),
+                _ => None,
+            })
+        }
+        Segment::Seq { index } => match schema.array.as_ref()?.items.as_ref()? {
+            SingleOrVec::Single(item) => Some(item),
+            SingleOrVec::Vec(items) => items.get(*index),
+        },
+        Segment::Unknown => None,
+    }
+}
+
+/// A brief description of the values a schema permits.
+fn describe(root: &RootSchema, schema: &Schema) -> String {
+    let obj = match schema {
+        Schema::Bool(true) => return "any value".to_string(),
+        Schema::Bool(false) => return "no value".to_string(),
+        Schema::Object(obj) => obj,
+    };
+
+    if let Some(reference) = &obj.reference {
+        let name = reference_name(reference);
+        return match root.definitions.get(name) {
+            Some(definition) => format!("{} ({})", name, describe(root, definition)),
+            None => name.to_string(),
+        };
+    }
+    if let Some(values) = &obj.enum_values {
+        let values = values.iter().map(ToString::to_string).collect::<Vec<_>>();
+        return format!("one of {}", values.join(", "));
+    }
+    if let Some(value) = &obj.const_value {
+        return format!("the constant {}", value);
+    }
+    if let Some(instance_type) = &obj.instance_type {
+        let types = match instance_type {
+            SingleOrVec::Single(t) => vec![**t],
+            SingleOrVec::Vec(types) => types.clone(),
+        };
+        let types = types
+            .iter()
+            .map(|t| {
+                serde_json::to_value(t)
+                    .ok()
+                    .and_then(|v| v.as_str().map(String::from))
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        return format!("a value of type {}", types.join(" or "));
+    }
+    if let Some(subschemas) = &obj.subschemas {
+        let variants = subschemas.one_of.iter().chain(&subschemas.any_of).flatten();
+        let names = variants.filter_map(variant_name).collect::<Vec<_>>();
+        if !names.is_empty() {
+            return format!("one of the variants {}", names.join(", "));
+        }
+    }
+    "any value".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use schemars::JsonSchema;
+    use serde::{Serialize, Serializer};
+
+    use crate::validate;
+
+    #[derive(Serialize, JsonSchema)]
+    struct Grid {
+        cells: Vec<Row>,
+    }
+
+    #[derive(Serialize, JsonSchema)]
+    struct Row {
+        #[schemars(with = "BTreeMap<String, u32>")]
+        counts: BTreeMap<(u32, u32), u32>,
+        label: Label,
+    }
+
+    struct Label(String);
+
+    impl Serialize for Label {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            if self.0.is_empty() {
+                return Err(serde::ser::Error::custom("labels must not be empty"));
+            }
+            serializer.serialize_str(&self.0)
+        }
+    }
+
+    impl JsonSchema for Label {
+        fn schema_name() -> String {
+            "Label".to_string()
+        }
+
+        fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+            String::json_schema(gen)
+        }
+    }
+
+    #[test]
+    fn test_custom_error() {
+        let item = Grid {
+            cells: vec![
+                Row {
+                    counts: BTreeMap::new(),
+                    label: Label("a".to_string()),
+                },
+                Row {
+                    counts: BTreeMap::new(),
+                    label: Label(String::new()),
+                },
+            ],
+        };
+        assert_eq!(
+            validate(&item).unwrap_err().to_string(),
+            "error serializing item at $.cells[1].label: labels must not be empty; \
+            schema expects Label (a value of type string) here",
+        );
+    }
+
+    #[test]
+    fn test_map_key() {
+        let item = Grid {
+            cells: vec![Row {
+                counts: [((1, 2), 3)].into_iter().collect(),
+                label: Label("a".to_string()),
+            }],
+        };
+        assert_eq!(
+            validate(&item).unwrap_err().to_string(),
+            "error serializing item at $.cells[0].counts: key must be a string; \
+            schema expects `propertyNames` a string here; serde_json can only serialize map \
+            keys that are strings, numbers, booleans, or unit variants",
+        );
+    }
+}
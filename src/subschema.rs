@@ -0,0 +1,1116 @@
+use std::fmt;
+
+use schemars::schema::{
+    ArrayValidation, InstanceType, NumberValidation, ObjectValidation, RootSchema, Schema,
+    SchemaObject, SingleOrVec, StringValidation,
+};
+use serde_json::{Map, Value};
+
+use crate::{
+    escape_pointer_segment,
+    metadata::{conform, sample},
+    schema::{reference_name, variant_name},
+};
+
+/// Keywords that describe a schema rather than the values it permits.
+const ANNOTATIONS: &[&str] = &["$comment", "$schema", "definitions"];
+
+/// A way in which a schema permits values that another doesn't; see
+/// [`is_subschema`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Incompatibility {
+    /// A JSON pointer to the value in question, with `*` standing for any
+    /// array item or any property not otherwise declared.
+    pub pointer: String,
+    pub details: String,
+    /// A value at the pointer that the first schema permits and the second
+    /// doesn't, if one was found.
+    pub witness: Option<Value>,
+}
+
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.pointer.as_str() {
+            "" => write!(f, "at the root: {}", self.details)?,
+            pointer => write!(f, "at {}: {}", pointer, self.details)?,
+        }
+        if let Some(witness) = &self.witness {
+            write!(f, " (for example, {})", witness)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check whether every value that one schema permits is also permitted by
+/// another, such as a new response type's schema and an old one's.
+///
+/// The check is conservative: it covers types, `enum` and `const`,
+/// `required`, `properties`, and `additionalProperties`, numeric, length,
+/// item, and property count bounds, `pattern`, `format`, array `items`,
+/// `allOf`, and `oneOf` / `anyOf` variants, and reports anything it can't
+/// show to be permitted, even if it might be, including any `not`, `if` /
+/// `then` / `else`, `contains`, `additionalItems`, `propertyNames`,
+/// `patternProperties`, or other keywords such as `dependencies` in the
+/// second schema. Each incompatibility comes with a value that demonstrates
+/// it when one can be found.
+pub fn is_subschema(a: &RootSchema, b: &RootSchema) -> Result<(), Vec<Incompatibility>> {
+    let title = |root: &RootSchema| {
+        root.schema
+            .metadata
+            .as_ref()
+            .and_then(|m| m.title.clone())
+            .unwrap_or_default()
+    };
+    let mut checker = Checker {
+        a,
+        b,
+        path: vec![(title(a), title(b))],
+        out: Vec::new(),
+    };
+    checker.check(
+        String::new(),
+        &Schema::Object(a.schema.clone()),
+        &Schema::Object(b.schema.clone()),
+    );
+
+    match checker.out.is_empty() {
+        true => Ok(()),
+        false => Err(checker.out),
+    }
+}
+
+struct Checker<'a> {
+    a: &'a RootSchema,
+    b: &'a RootSchema,
+    /// The definitions that each schema was within at each reference
+    /// followed to reach the current value; reaching the same pair again
+    /// means we're going around a recursive type.
+    path: Vec<(String, String)>,
+    out: Vec<Incompatibility>,
+}
+
+impl Checker<'_> {
+    fn check(&mut self, pointer: String, a: &Schema, b: &Schema) {
+        // Merging an `allOf` can only loosen a schema, which is safe for the
+        // first but not the second; its `allOf` is checked part by part.
+        let (a, a_name) = expand(self.a, a);
+        let (b, b_name) = resolve(self.b, b);
+
+        let followed = a_name.is_some() || b_name.is_some();
+        if followed {
+            let (a_latest, b_latest) = self.path.last().cloned().unwrap_or_default();
+            let pair = (a_name.unwrap_or(a_latest), b_name.unwrap_or(b_latest));
+            if self.path.contains(&pair) {
+                return;
+            }
+            self.path.push(pair);
+        }
+
+        match (&a, &b) {
+            (_, Schema::Bool(true)) | (Schema::Bool(false), _) => (),
+            (Schema::Bool(true), Schema::Bool(false)) => {
+                self.report(pointer, "the second schema permits no value here", None)
+            }
+            (Schema::Object(a), Schema::Bool(false)) => {
+                let witness = sample(self.a, a, 0);
+                self.report(pointer, "the second schema permits no value here", witness)
+            }
+            (Schema::Bool(true), Schema::Object(b)) => {
+                self.check_objects(pointer, &SchemaObject::default(), b)
+            }
+            (Schema::Object(a), Schema::Object(b)) => self.check_objects(pointer, a, b),
+        }
+
+        if followed {
+            self.path.pop();
+        }
+    }
+
+    fn check_objects(&mut self, pointer: String, a: &SchemaObject, b: &SchemaObject) {
+        // Each of the first schema's variants must be permitted.
+        if let Some(branches) = union(a) {
+            for branch in branches {
+                self.check(pointer.clone(), branch, &Schema::Object(b.clone()));
+            }
+            return;
+        }
+
+        // A finite set of values is easily checked, whatever the second
+        // schema is.
+        if let Some(values) = enumerated(a) {
+            for value in values {
+                if !conform(self.b, b, &value).is_empty() {
+                    self.push(
+                        pointer.clone(),
+                        format!("the first schema permits {}, but the second doesn't", value),
+                        Some(value),
+                    );
+                }
+            }
+            return;
+        }
+
+        // A value must be permitted by each of the second's `allOf`.
+        if let Some(all_of) = b.subschemas.as_ref().and_then(|s| s.all_of.as_ref()) {
+            let mut rest = b.clone();
+            if let Some(subschemas) = rest.subschemas.as_deref_mut() {
+                subschemas.all_of = None;
+            }
+            self.check_objects(pointer.clone(), a, &rest);
+            for part in all_of {
+                self.check(pointer.clone(), &Schema::Object(a.clone()), part);
+            }
+            return;
+        }
+
+        // Anything else the second schema requires of a value can't be shown
+        // to hold, so we report it rather than ignore it.
+        let mut unmodeled = Vec::new();
+        if let Some(subschemas) = b.subschemas.as_deref() {
+            unmodeled.extend(
+                [
+                    ("not", subschemas.not.is_some()),
+                    ("if", subschemas.if_schema.is_some()),
+                    ("then", subschemas.then_schema.is_some()),
+                    ("else", subschemas.else_schema.is_some()),
+                ]
+                .into_iter()
+                .filter(|(_, present)| *present)
+                .map(|(keyword, _)| keyword.to_string()),
+            );
+        }
+        let extensions = b
+            .extensions
+            .iter()
+            .filter(|(keyword, value)| {
+                !ANNOTATIONS.contains(&keyword.as_str())
+                    && !keyword.starts_with("x-")
+                    && a.extensions.get(*keyword) != Some(value)
+            })
+            .map(|(keyword, _)| keyword.clone())
+            .collect::<Vec<_>>();
+        unmodeled.extend(extensions.iter().cloned());
+        if !unmodeled.is_empty() {
+            self.unmodeled(&pointer, a, b, &unmodeled);
+            let mut rest = b.clone();
+            if let Some(subschemas) = rest.subschemas.as_deref_mut() {
+                subschemas.not = None;
+                subschemas.if_schema = None;
+                subschemas.then_schema = None;
+                subschemas.else_schema = None;
+            }
+            rest.extensions
+                .retain(|keyword, _| !extensions.contains(keyword));
+            return self.check_objects(pointer, a, &rest);
+        }
+
+        // And by one of the second's variants.
+        if let Some(branches) = union(b) {
+            let mut rest = b.clone();
+            if let Some(subschemas) = rest.subschemas.as_deref_mut() {
+                if subschemas.one_of.take().is_none() {
+                    subschemas.any_of = None;
+                }
+            }
+            self.check_objects(pointer.clone(), a, &rest);
+
+            let mut closest = None::<(String, Vec<Incompatibility>)>;
+            for branch in branches {
+                let len = self.out.len();
+                self.check(pointer.clone(), &Schema::Object(a.clone()), branch);
+                let found = self.out.split_off(len);
+                if found.is_empty() {
+                    return;
+                }
+                if closest
+                    .as_ref()
+                    .is_none_or(|(_, other)| found.len() < other.len())
+                {
+                    let name = variant_name(branch).unwrap_or_else(|| "?".to_string());
+                    closest = Some((name, found));
+                }
+            }
+            let details = match closest {
+                Some((name, found)) => format!(
+                    "none of the second schema's variants permit the value; the closest is {} \
+                    ({})",
+                    name, found[0],
+                ),
+                None => "the second schema has no variants".to_string(),
+            };
+            let witness = self.witness(a, b, sample(self.a, a, 0));
+            self.push(pointer, details, witness);
+            return;
+        }
+
+        if let Some(values) = enumerated(b) {
+            let values = values.iter().map(ToString::to_string).collect::<Vec<_>>();
+            let witness = self.witness(a, b, sample(self.a, a, 0));
+            self.push(
+                pointer,
+                format!(
+                    "the second schema only permits {}, but the first permits others",
+                    values.join(", ")
+                ),
+                witness,
+            );
+            return;
+        }
+
+        let a_types = types(a);
+        let b_types = types(b);
+        if let Some(b_types) = &b_types {
+            match &a_types {
+                None => {
+                    let candidate = [
+                        Value::Null,
+                        Value::Bool(false),
+                        Value::from(0),
+                        Value::String(String::new()),
+                        Value::Array(Vec::new()),
+                        Value::Object(Map::new()),
+                    ]
+                    .into_iter()
+                    .find(|value| !b_types.iter().any(|t| is_instance(*t, value)));
+                    let witness = self.witness(a, b, candidate);
+                    self.push(
+                        pointer.clone(),
+                        format!(
+                            "the first schema permits any type, but the second only {}",
+                            display_types(b_types)
+                        ),
+                        witness,
+                    );
+                }
+                Some(a_types) => {
+                    for t in a_types {
+                        let covered = b_types.contains(t)
+                            || (*t == InstanceType::Integer
+                                && b_types.contains(&InstanceType::Number));
+                        if !covered {
+                            let mut only = a.clone();
+                            only.instance_type = Some(SingleOrVec::Single(Box::new(*t)));
+                            let witness = self.witness(a, b, sample(self.a, &only, 0));
+                            self.push(
+                                pointer.clone(),
+                                format!(
+                                    "the first schema permits {}, but the second only {}",
+                                    display_types(&[*t]),
+                                    display_types(b_types)
+                                ),
+                                witness,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        let permits = |t: InstanceType| {
+            a_types.as_ref().is_none_or(|types| {
+                types.contains(&t)
+                    || (t == InstanceType::Number && types.contains(&InstanceType::Integer))
+            })
+        };
+
+        if permits(InstanceType::Number) {
+            self.check_numbers(&pointer, a, b);
+        }
+        if permits(InstanceType::String) {
+            self.check_strings(&pointer, a, b);
+        }
+        if permits(InstanceType::Array) {
+            self.check_arrays(&pointer, a, b);
+        }
+        if permits(InstanceType::Object) {
+            self.check_properties(&pointer, a, b);
+        }
+    }
+
+    fn check_numbers(&mut self, pointer: &str, a: &SchemaObject, b: &SchemaObject) {
+        let default = NumberValidation::default();
+        let a_number = a.number.as_deref().unwrap_or(&default);
+        let Some(b_number) = b.number.as_deref() else {
+            return;
+        };
+
+        let a_lower = bound(a_number.minimum, a_number.exclusive_minimum, f64::max);
+        let b_lower = bound(b_number.minimum, b_number.exclusive_minimum, f64::max);
+        if let Some((b_value, b_exclusive)) = b_lower {
+            if !within(a_lower, b_value, b_exclusive, |a, b| a > b) {
+                let candidate = if b_exclusive { b_value } else { b_value - 1.0 };
+                let witness = self.witness(a, b, Some(number(candidate)));
+                self.push(
+                    pointer.to_string(),
+                    format!(
+                        "the first schema permits numbers below the second's {} of {}",
+                        if b_exclusive {
+                            "exclusive minimum"
+                        } else {
+                            "minimum"
+                        },
+                        b_value
+                    ),
+                    witness,
+                );
+            }
+        }
+
+        let a_upper = bound(a_number.maximum, a_number.exclusive_maximum, f64::min);
+        let b_upper = bound(b_number.maximum, b_number.exclusive_maximum, f64::min);
+        if let Some((b_value, b_exclusive)) = b_upper {
+            if !within(a_upper, b_value, b_exclusive, |a, b| a < b) {
+                let candidate = if b_exclusive { b_value } else { b_value + 1.0 };
+                let witness = self.witness(a, b, Some(number(candidate)));
+                self.push(
+                    pointer.to_string(),
+                    format!(
+                        "the first schema permits numbers above the second's {} of {}",
+                        if b_exclusive {
+                            "exclusive maximum"
+                        } else {
+                            "maximum"
+                        },
+                        b_value
+                    ),
+                    witness,
+                );
+            }
+        }
+
+        if let Some(b_multiple) = b_number.multiple_of {
+            let multiple = a_number
+                .multiple_of
+                .is_some_and(|a_multiple| (a_multiple / b_multiple).fract() == 0.0);
+            if !multiple {
+                let witness = self.witness(a, b, Some(number(b_multiple / 2.0)));
+                self.push(
+                    pointer.to_string(),
+                    format!(
+                        "the first schema permits numbers that aren't multiples of {}",
+                        b_multiple
+                    ),
+                    witness,
+                );
+            }
+        }
+    }
+
+    fn check_strings(&mut self, pointer: &str, a: &SchemaObject, b: &SchemaObject) {
+        let default = StringValidation::default();
+        let a_string = a.string.as_deref().unwrap_or(&default);
+        if let Some(b_string) = b.string.as_deref() {
+            self.check_counts(
+                pointer,
+                a,
+                b,
+                "length",
+                (a_string.min_length, a_string.max_length),
+                (b_string.min_length, b_string.max_length),
+                |n| Value::String("x".repeat(n)),
+            );
+
+            if let Some(pattern) = &b_string.pattern {
+                if a_string.pattern.as_ref() != Some(pattern) {
+                    let witness = self.witness(a, b, Some(Value::String(String::new())));
+                    self.push(
+                        pointer.to_string(),
+                        format!(
+                            "the second schema requires strings to match the pattern {}",
+                            pattern
+                        ),
+                        witness,
+                    );
+                }
+            }
+        }
+
+        if let Some(format) = &b.format {
+            if a.format.as_ref() != Some(format) {
+                self.push(
+                    pointer.to_string(),
+                    format!("the second schema requires the format {}", format),
+                    None,
+                );
+            }
+        }
+    }
+
+    fn check_arrays(&mut self, pointer: &str, a: &SchemaObject, b: &SchemaObject) {
+        let default = ArrayValidation::default();
+        let a_array = a.array.as_deref().unwrap_or(&default);
+        let Some(b_array) = b.array.as_deref() else {
+            return;
+        };
+
+        let anything = Schema::Bool(true);
+        match (&a_array.items, &b_array.items) {
+            (_, None) => (),
+            (None, Some(SingleOrVec::Single(b_items))) => {
+                self.check(format!("{}/*", pointer), &anything, b_items)
+            }
+            (Some(SingleOrVec::Single(a_items)), Some(SingleOrVec::Single(b_items))) => {
+                self.check(format!("{}/*", pointer), a_items, b_items)
+            }
+            (Some(SingleOrVec::Vec(a_items)), Some(SingleOrVec::Single(b_items))) => {
+                for (i, a_item) in a_items.iter().enumerate() {
+                    self.check(format!("{}/{}", pointer, i), a_item, b_items);
+                }
+            }
+            (Some(SingleOrVec::Vec(a_items)), Some(SingleOrVec::Vec(b_items)))
+                if a_items.len() == b_items.len() =>
+            {
+                for (i, (a_item, b_item)) in a_items.iter().zip(b_items).enumerate() {
+                    self.check(format!("{}/{}", pointer, i), a_item, b_item);
+                }
+            }
+            _ => self.push(
+                pointer.to_string(),
+                "the schemas' array items can't be compared".to_string(),
+                None,
+            ),
+        }
+
+        self.check_counts(
+            pointer,
+            a,
+            b,
+            "number of items",
+            (a_array.min_items, a_array.max_items),
+            (b_array.min_items, b_array.max_items),
+            |n| Value::Array(vec![Value::Null; n]),
+        );
+
+        if b_array.unique_items == Some(true) && a_array.unique_items != Some(true) {
+            let witness = self.witness(a, b, Some(Value::Array(vec![Value::Null; 2])));
+            self.push(
+                pointer.to_string(),
+                "the second schema requires unique items".to_string(),
+                witness,
+            );
+        }
+
+        let mut unmodeled = Vec::new();
+        if b_array.contains.is_some() && a_array.contains != b_array.contains {
+            unmodeled.push("contains".to_string());
+        }
+        // Additional items only apply after a tuple's.
+        if matches!(b_array.items, Some(SingleOrVec::Vec(_)))
+            && b_array.additional_items.is_some()
+            && a_array.additional_items != b_array.additional_items
+        {
+            unmodeled.push("additionalItems".to_string());
+        }
+        self.unmodeled(pointer, a, b, &unmodeled);
+    }
+
+    fn check_properties(&mut self, pointer: &str, a: &SchemaObject, b: &SchemaObject) {
+        let default = ObjectValidation::default();
+        let a_object = a.object.as_deref().unwrap_or(&default);
+        let Some(b_object) = b.object.as_deref() else {
+            return;
+        };
+        let child = |prop: &str| format!("{}/{}", pointer, escape_pointer_segment(prop));
+        let anything = Schema::Bool(true);
+        let a_additional = a_object
+            .additional_properties
+            .as_deref()
+            .unwrap_or(&anything);
+        let b_additional = b_object
+            .additional_properties
+            .as_deref()
+            .unwrap_or(&anything);
+
+        self.check_counts(
+            pointer,
+            a,
+            b,
+            "number of properties",
+            (a_object.min_properties, a_object.max_properties),
+            (b_object.min_properties, b_object.max_properties),
+            |n| Value::Object((0..n).map(|i| (format!("p{}", i), Value::Null)).collect()),
+        );
+
+        let mut unmodeled = Vec::new();
+        if b_object.property_names.is_some() && a_object.property_names != b_object.property_names {
+            unmodeled.push("propertyNames".to_string());
+        }
+        if !b_object.pattern_properties.is_empty()
+            && a_object.pattern_properties != b_object.pattern_properties
+        {
+            unmodeled.push("patternProperties".to_string());
+        }
+        self.unmodeled(pointer, a, b, &unmodeled);
+
+        for prop in &b_object.required {
+            if !a_object.required.contains(prop) {
+                // Samples include only required properties.
+                let witness = self.witness(a, b, sample(self.a, a, 0));
+                self.push(
+                    child(prop),
+                    format!(
+                        "the second schema requires the property {}, but the first doesn't",
+                        prop
+                    ),
+                    witness,
+                );
+            }
+        }
+
+        for (prop, a_prop) in &a_object.properties {
+            match b_object.properties.get(prop) {
+                Some(b_prop) => self.check(child(prop), a_prop, b_prop),
+                None if b_additional == &Schema::Bool(false)
+                    && b_object.pattern_properties.is_empty() =>
+                {
+                    let witness = self
+                        .with_property(a, prop, a_prop)
+                        .and_then(|value| self.witness(a, b, Some(value)));
+                    self.push(
+                        child(prop),
+                        format!(
+                            "the first schema permits the property {}, but the second doesn't",
+                            prop
+                        ),
+                        witness,
+                    );
+                }
+                None => self.check(child(prop), a_prop, b_additional),
+            }
+        }
+
+        // Properties the first schema doesn't declare may have any value it
+        // permits for additional properties.
+        if a_additional != &Schema::Bool(false) {
+            for (prop, b_prop) in &b_object.properties {
+                if !a_object.properties.contains_key(prop) {
+                    self.check(child(prop), a_additional, b_prop);
+                }
+            }
+            if b_additional == &Schema::Bool(false) && b_object.pattern_properties.is_empty() {
+                let witness = self
+                    .with_property(a, "does_it_json_unknown", a_additional)
+                    .and_then(|value| self.witness(a, b, Some(value)));
+                self.push(
+                    child("*"),
+                    "the first schema permits undeclared properties, but the second doesn't"
+                        .to_string(),
+                    witness,
+                );
+            } else if b_object.pattern_properties.is_empty() {
+                self.check(child("*"), a_additional, b_additional);
+            }
+        }
+    }
+
+    /// Check that the first schema's bounds on a count, such as the length
+    /// of a string, are within the second's.
+    #[allow(clippy::too_many_arguments)]
+    fn check_counts(
+        &mut self,
+        pointer: &str,
+        a: &SchemaObject,
+        b: &SchemaObject,
+        what: &str,
+        (a_min, a_max): (Option<u32>, Option<u32>),
+        (b_min, b_max): (Option<u32>, Option<u32>),
+        example: impl Fn(usize) -> Value,
+    ) {
+        if let Some(b_min) = b_min.filter(|b_min| a_min.unwrap_or(0) < *b_min) {
+            let witness = self.witness(a, b, Some(example(b_min as usize - 1)));
+            self.push(
+                pointer.to_string(),
+                format!(
+                    "the first schema permits a {} below the second's minimum of {}",
+                    what, b_min
+                ),
+                witness,
+            );
+        }
+        if let Some(b_max) = b_max.filter(|b_max| a_max.is_none_or(|a_max| a_max > *b_max)) {
+            let witness = self.witness(a, b, Some(example(b_max as usize + 1)));
+            self.push(
+                pointer.to_string(),
+                format!(
+                    "the first schema permits a {} above the second's maximum of {}",
+                    what, b_max
+                ),
+                witness,
+            );
+        }
+    }
+
+    /// Report keywords of the second schema that we can't compare against,
+    /// if there are any.
+    fn unmodeled(
+        &mut self,
+        pointer: &str,
+        a: &SchemaObject,
+        b: &SchemaObject,
+        keywords: &[String],
+    ) {
+        if keywords.is_empty() {
+            return;
+        }
+        let keywords = keywords
+            .iter()
+            .map(|keyword| format!("`{}`", keyword))
+            .collect::<Vec<_>>();
+        let witness = self.witness(a, b, sample(self.a, a, 0));
+        self.push(
+            pointer.to_string(),
+            format!(
+                "can't compare against the second schema's {}",
+                keywords.join(", ")
+            ),
+            witness,
+        );
+    }
+
+    /// A sample of the first schema with the given property added.
+    fn with_property(&self, a: &SchemaObject, prop: &str, prop_schema: &Schema) -> Option<Value> {
+        let Value::Object(mut map) = sample(self.a, a, 0)? else {
+            return None;
+        };
+        let value = match prop_schema {
+            Schema::Bool(true) => Value::Null,
+            Schema::Bool(false) => return None,
+            Schema::Object(obj) => sample(self.a, obj, 0)?,
+        };
+        map.insert(prop.to_string(), value);
+        Some(Value::Object(map))
+    }
+
+    /// The candidate, if the first schema permits it and the second doesn't.
+    fn witness(
+        &self,
+        a: &SchemaObject,
+        b: &SchemaObject,
+        candidate: Option<Value>,
+    ) -> Option<Value> {
+        candidate.filter(|value| {
+            conform(self.a, a, value).is_empty() && !conform(self.b, b, value).is_empty()
+        })
+    }
+
+    fn report(&mut self, pointer: String, details: &str, witness: Option<Value>) {
+        self.push(pointer, details.to_string(), witness)
+    }
+
+    fn push(&mut self, pointer: String, details: String, witness: Option<Value>) {
+        self.out.push(Incompatibility {
+            pointer,
+            details,
+            witness,
+        });
+    }
+}
+
+/// Follow references and combine an `allOf` of object schemas into one,
+/// returning the name of the last definition followed.
+fn expand(root: &RootSchema, schema: &Schema) -> (Schema, Option<String>) {
+    let (schema, name) = resolve(root, schema);
+    if let Schema::Object(obj) = &schema {
+        if let Some(merged) = merge_all_of(root, obj) {
+            return (Schema::Object(merged), name);
+        }
+    }
+    (schema, name)
+}
+
+/// Follow references, returning the name of the last definition followed.
+fn resolve(root: &RootSchema, schema: &Schema) -> (Schema, Option<String>) {
+    let mut schema = schema.clone();
+    let mut name = None;
+    while let Schema::Object(SchemaObject {
+        reference: Some(reference),
+        ..
+    }) = &schema
+    {
+        let definition = reference_name(reference).to_string();
+        let Some(resolved) = root.definitions.get(&definition) else {
+            break;
+        };
+        schema = resolved.clone();
+        name = Some(definition);
+    }
+    (schema, name)
+}
+
+/// Combine an `allOf` (as for flattened structs) into a single schema,
+/// provided its parts only describe objects' properties.
+fn merge_all_of(root: &RootSchema, schema: &SchemaObject) -> Option<SchemaObject> {
+    let subschemas = schema.subschemas.as_deref()?;
+    let all_of = subschemas.all_of.as_ref()?;
+    if subschemas.any_of.is_some() || subschemas.one_of.is_some() || subschemas.not.is_some() {
+        return None;
+    }
+
+    let mut merged = schema.clone();
+    merged.subschemas = None;
+    if let [only] = all_of.as_slice() {
+        return match expand(root, only).0 {
+            Schema::Object(only) if merged.object.is_none() => {
+                let metadata = merged.metadata.take();
+                Some(SchemaObject { metadata, ..only })
+            }
+            _ => None,
+        };
+    }
+
+    let object = merged.object.get_or_insert_with(Default::default);
+    for part in all_of {
+        let Schema::Object(part) = expand(root, part).0 else {
+            return None;
+        };
+        let SchemaObject {
+            instance_type,
+            object: Some(part_object),
+            subschemas: None,
+            reference: None,
+            enum_values: None,
+            const_value: None,
+            ..
+        } = part
+        else {
+            return None;
+        };
+        if instance_type.is_some_and(|t| t != SingleOrVec::Single(Box::new(InstanceType::Object))) {
+            return None;
+        }
+        object.properties.extend(part_object.properties);
+        object.required.extend(part_object.required);
+        if part_object.additional_properties.is_some() {
+            object.additional_properties = part_object.additional_properties;
+        }
+    }
+    merged.instance_type = Some(SingleOrVec::Single(Box::new(InstanceType::Object)));
+    Some(merged)
+}
+
+/// The variants of a schema that is a `oneOf` or `anyOf`.
+fn union(schema: &SchemaObject) -> Option<&Vec<Schema>> {
+    let subschemas = schema.subschemas.as_deref()?;
+    subschemas.one_of.as_ref().or(subschemas.any_of.as_ref())
+}
+
+/// The values a schema enumerates with `enum` or `const`, if it does.
+fn enumerated(schema: &SchemaObject) -> Option<Vec<Value>> {
+    match (&schema.const_value, &schema.enum_values) {
+        (Some(value), _) => Some(vec![value.clone()]),
+        (None, Some(values)) => Some(values.clone()),
+        (None, None) => None,
+    }
+}
+
+fn types(schema: &SchemaObject) -> Option<Vec<InstanceType>> {
+    match schema.instance_type.as_ref()? {
+        SingleOrVec::Single(t) => Some(vec![**t]),
+        SingleOrVec::Vec(types) => Some(types.clone()),
+    }
+}
+
+fn display_types(types: &[InstanceType]) -> String {
+    types
+        .iter()
+        .map(|t| {
+            serde_json::to_value(t)
+                .ok()
+                .and_then(|v| v.as_str().map(String::from))
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+fn is_instance(instance_type: InstanceType, value: &Value) -> bool {
+    match instance_type {
+        InstanceType::Null => value.is_null(),
+        InstanceType::Boolean => value.is_boolean(),
+        InstanceType::Object => value.is_object(),
+        InstanceType::Array => value.is_array(),
+        InstanceType::Number => value.is_number(),
+        InstanceType::String => value.is_string(),
+        InstanceType::Integer => value.is_u64() || value.is_i64(),
+    }
+}
+
+/// The tighter of an inclusive and an exclusive bound, and whether it's
+/// exclusive.
+fn bound(
+    inclusive: Option<f64>,
+    exclusive: Option<f64>,
+    tighter: fn(f64, f64) -> f64,
+) -> Option<(f64, bool)> {
+    match (inclusive, exclusive) {
+        (Some(i), Some(e)) if tighter(i, e) == i && i != e => Some((i, false)),
+        (_, Some(e)) => Some((e, true)),
+        (Some(i), None) => Some((i, false)),
+        (None, None) => None,
+    }
+}
+
+/// Whether the first schema's bound is at least as tight as the second's,
+/// where `beyond` says whether one bound value is tighter than another.
+fn within(
+    a: Option<(f64, bool)>,
+    b_value: f64,
+    b_exclusive: bool,
+    beyond: fn(f64, f64) -> bool,
+) -> bool {
+    match a {
+        None => false,
+        Some((a_value, a_exclusive)) => {
+            beyond(a_value, b_value) || (a_value == b_value && (a_exclusive || !b_exclusive))
+        }
+    }
+}
+
+/// A number, as an integer if it is one.
+fn number(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        Value::from(value as i64)
+    } else {
+        Value::from(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::{schema::RootSchema, schema_for, JsonSchema};
+    use serde_json::json;
+
+    use super::{is_subschema, Incompatibility};
+
+    #[allow(dead_code)]
+    mod old {
+        use schemars::JsonSchema;
+
+        #[derive(JsonSchema)]
+        pub struct Response {
+            pub id: u64,
+            pub name: Option<String>,
+            pub status: Status,
+            pub children: Vec<Response>,
+        }
+
+        #[derive(JsonSchema)]
+        pub enum Status {
+            Active,
+            Retired,
+        }
+    }
+
+    #[allow(dead_code)]
+    mod new {
+        use schemars::JsonSchema;
+
+        #[derive(JsonSchema)]
+        #[schemars(deny_unknown_fields)]
+        pub struct Response {
+            #[schemars(range(min = 1))]
+            pub id: u64,
+            pub name: String,
+            pub status: Status,
+            pub children: Vec<Response>,
+        }
+
+        #[derive(JsonSchema)]
+        pub enum Status {
+            Active,
+        }
+    }
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    enum Wider {
+        Active,
+        Retired,
+        Missing,
+    }
+
+    #[test]
+    fn test_subschema() {
+        // Responses only get more specific.
+        assert_eq!(
+            is_subschema(&schema_for!(new::Response), &schema_for!(old::Response)),
+            Ok(())
+        );
+
+        let errors =
+            is_subschema(&schema_for!(old::Response), &schema_for!(new::Response)).unwrap_err();
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "at /name: the second schema requires the property name, but the first doesn't \
+                (for example, {\"children\":[],\"id\":0,\"status\":\"Active\"})",
+                "at /id: the first schema permits numbers below the second's minimum of 1 (for \
+                example, 0)",
+                "at /name: the first schema permits null, but the second only string (for \
+                example, null)",
+                "at /status: the first schema permits \"Retired\", but the second doesn't (for \
+                example, \"Retired\")",
+                "at /*: the first schema permits undeclared properties, but the second doesn't \
+                (for example, {\"children\":[],\"does_it_json_unknown\":null,\"id\":0,\"status\":\"Active\"})",
+            ],
+        );
+
+        assert_eq!(
+            is_subschema(&schema_for!(Wider), &schema_for!(old::Status)),
+            Err(vec![Incompatibility {
+                pointer: String::new(),
+                details: "the first schema permits \"Missing\", but the second doesn't".to_string(),
+                witness: Some(json!("Missing")),
+            }]),
+        );
+    }
+
+    #[test]
+    fn test_subschemas() {
+        let root = |schema| serde_json::from_value::<RootSchema>(schema).unwrap();
+        let check = |a, b| {
+            is_subschema(&root(a), &root(b))
+                .map_err(|errors| errors.iter().map(ToString::to_string).collect::<Vec<_>>())
+        };
+
+        // Each part of an `allOf` constrains the value.
+        let bounded = json!({
+            "type": "string",
+            "allOf": [{ "minLength": 3 }, { "maxLength": 5 }],
+        });
+        assert_eq!(
+            check(
+                json!({ "type": "string", "minLength": 4, "maxLength": 5 }),
+                bounded.clone()
+            ),
+            Ok(()),
+        );
+        assert_eq!(
+            check(json!({ "type": "string" }), bounded),
+            Err(vec![
+                "at the root: the first schema permits a length below the second's minimum of 3 \
+                (for example, \"xx\")"
+                    .to_string(),
+                "at the root: the first schema permits a length above the second's maximum of 5 \
+                (for example, \"xxxxxx\")"
+                    .to_string(),
+            ]),
+        );
+
+        // Other subschemas aren't modeled, so they can't be shown to permit
+        // the value.
+        assert_eq!(
+            check(
+                json!({ "type": "string" }),
+                json!({ "type": "string", "not": { "const": "x" } }),
+            ),
+            Err(vec![
+                "at the root: can't compare against the second schema's `not`".to_string()
+            ]),
+        );
+        assert_eq!(
+            check(
+                json!({ "type": "string" }),
+                json!({
+                    "type": "string",
+                    "if": { "minLength": 1 },
+                    "then": { "maxLength": 3 },
+                }),
+            ),
+            Err(vec![
+                "at the root: can't compare against the second schema's `if`, `then`".to_string()
+            ]),
+        );
+        assert_eq!(
+            check(
+                json!({ "type": "array" }),
+                json!({ "type": "array", "contains": { "type": "number" } }),
+            ),
+            Err(vec![
+                "at the root: can't compare against the second schema's `contains` \
+                (for example, [])"
+                    .to_string()
+            ]),
+        );
+        assert_eq!(
+            check(
+                json!({ "type": "array", "items": [{ "type": "number" }] }),
+                json!({
+                    "type": "array",
+                    "items": [{ "type": "number" }],
+                    "additionalItems": false,
+                }),
+            ),
+            Err(vec![
+                "at the root: can't compare against the second schema's `additionalItems`"
+                    .to_string()
+            ]),
+        );
+        assert_eq!(
+            check(
+                json!({ "type": "object" }),
+                json!({ "type": "object", "propertyNames": { "maxLength": 3 } }),
+            ),
+            Err(vec![
+                "at the root: can't compare against the second schema's `propertyNames`"
+                    .to_string()
+            ]),
+        );
+        assert_eq!(
+            check(
+                json!({ "type": "object" }),
+                json!({
+                    "type": "object",
+                    "patternProperties": { "^x-": { "type": "string" } },
+                }),
+            ),
+            Err(vec![
+                "at the root: can't compare against the second schema's `patternProperties`"
+                    .to_string()
+            ]),
+        );
+        assert_eq!(
+            check(
+                json!({ "type": "object" }),
+                json!({ "type": "object", "dependencies": { "a": ["b"] } }),
+            ),
+            Err(vec![
+                "at the root: can't compare against the second schema's `dependencies`".to_string()
+            ]),
+        );
+
+        // Annotations and vendor extensions don't affect what's permitted.
+        assert_eq!(
+            check(
+                json!({ "type": "object" }),
+                json!({ "type": "object", "$comment": "c", "x-internal": true }),
+            ),
+            Ok(()),
+        );
+        // The same keyword in both schemas permits the same values.
+        let contains = json!({ "type": "array", "contains": { "type": "number" } });
+        assert_eq!(check(contains.clone(), contains), Ok(()));
+
+        // Property counts are bounded like item counts.
+        assert_eq!(
+            check(
+                json!({ "type": "object" }),
+                json!({ "type": "object", "minProperties": 1, "maxProperties": 2 }),
+            ),
+            Err(vec![
+                "at the root: the first schema permits a number of properties below the \
+                second's minimum of 1 (for example, {})"
+                    .to_string(),
+                "at the root: the first schema permits a number of properties above the \
+                second's maximum of 2 (for example, {\"p0\":null,\"p1\":null,\"p2\":null})"
+                    .to_string(),
+            ]),
+        );
+        assert_eq!(
+            check(
+                json!({ "type": "object", "maxProperties": 2 }),
+                json!({ "type": "object", "maxProperties": 3 }),
+            ),
+            Ok(()),
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use serde::{
+    de::{DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    Deserializer,
+};
+use serde_json::Value;
+
+use crate::escape_pointer_segment;
+
+/// A parsed JSON value that keeps object properties in the order in which
+/// they were written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ordered {
+    Scalar(Value),
+    Array(Vec<Ordered>),
+    Object(Vec<(String, Ordered)>),
+}
+
+impl Ordered {
+    pub fn parse(bytes: &[u8]) -> Result<Self, serde_json::Error> {
+        Ordered::parse_strict(bytes).map(|(ordered, _)| ordered)
+    }
+
+    /// Parse a value along with its duplicate keys, each given as a JSON
+    /// pointer to the object and the key.
+    pub fn parse_strict(bytes: &[u8]) -> Result<(Self, Vec<(String, String)>), serde_json::Error> {
+        let duplicates = RefCell::new(Vec::new());
+        let mut de = serde_json::Deserializer::from_slice(bytes);
+        let ordered = Strict {
+            pointer: String::new(),
+            duplicates: &duplicates,
+        }
+        .deserialize(&mut de)?;
+        de.end()?;
+        Ok((ordered, duplicates.into_inner()))
+    }
+
+    /// The order in which each object's properties were written, keyed by a
+    /// JSON pointer to the object.
+    pub fn property_orders(&self) -> BTreeMap<String, Vec<String>> {
+        let mut orders = BTreeMap::new();
+        self.collect_orders(String::new(), &mut orders);
+        orders
+    }
+
+    fn collect_orders(&self, pointer: String, orders: &mut BTreeMap<String, Vec<String>>) {
+        let child = |segment: &str| format!("{}/{}", pointer, escape_pointer_segment(segment));
+        match self {
+            Ordered::Scalar(_) => (),
+            Ordered::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    item.collect_orders(child(&i.to_string()), orders);
+                }
+            }
+            Ordered::Object(props) => {
+                for (key, value) in props {
+                    value.collect_orders(child(key), orders);
+                }
+                orders.insert(pointer, props.iter().map(|(key, _)| key.clone()).collect());
+            }
+        }
+    }
+
+    /// The value, in which the last of any duplicate properties wins as it
+    /// does with `serde_json::from_slice`.
+    pub fn to_value(&self) -> Value {
+        match self {
+            Ordered::Scalar(value) => value.clone(),
+            Ordered::Array(items) => Value::Array(items.iter().map(Ordered::to_value).collect()),
+            Ordered::Object(props) => Value::Object(
+                props
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.to_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Deserializes a value with its properties in the order in which they were
+/// written, recording any duplicate keys.
+struct Strict<'a> {
+    pointer: String,
+    duplicates: &'a RefCell<Vec<(String, String)>>,
+}
+
+impl<'a> Strict<'a> {
+    fn child(&self, segment: &str) -> Self {
+        Strict {
+            pointer: format!("{}/{}", self.pointer, escape_pointer_segment(segment)),
+            duplicates: self.duplicates,
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Strict<'_> {
+    type Value = Ordered;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Ordered, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Strict<'_> {
+    type Value = Ordered;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Ordered, E> {
+        Ok(Ordered::Scalar(Value::Bool(v)))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Ordered, E> {
+        Ok(Ordered::Scalar(Value::from(v)))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Ordered, E> {
+        Ok(Ordered::Scalar(Value::from(v)))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Ordered, E> {
+        Ok(Ordered::Scalar(Value::from(v)))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Ordered, E> {
+        Ok(Ordered::Scalar(Value::String(v.to_string())))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Ordered, E> {
+        Ok(Ordered::Scalar(Value::String(v)))
+    }
+
+    fn visit_unit<E>(self) -> Result<Ordered, E> {
+        Ok(Ordered::Scalar(Value::Null))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Ordered, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element_seed(self.child(&items.len().to_string()))? {
+            items.push(item);
+        }
+        Ok(Ordered::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Ordered, A::Error> {
+        let mut props = Vec::new();
+        let mut seen = BTreeSet::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let value = map.next_value_seed(self.child(&key))?;
+            if !seen.insert(key.clone()) {
+                self.duplicates
+                    .borrow_mut()
+                    .push((self.pointer.clone(), key.clone()));
+            }
+            props.push((key, value));
+        }
+        Ok(Ordered::Object(props))
+    }
+}
+
+/// A parsed JSON value whose structure [`difference`] can walk.
+pub trait Tree: PartialEq + Sized {
+    fn to_value(&self) -> Value;
+    fn items(&self) -> Option<&[Self]>;
+    /// An object's properties, in the order in which they were written if
+    /// that's known.
+    fn properties(&self) -> Option<Vec<(&str, &Self)>>;
+}
+
+impl Tree for Value {
+    fn to_value(&self) -> Value {
+        self.clone()
+    }
+
+    fn items(&self) -> Option<&[Self]> {
+        self.as_array().map(Vec::as_slice)
+    }
+
+    fn properties(&self) -> Option<Vec<(&str, &Self)>> {
+        let map = self.as_object()?;
+        Some(
+            map.iter()
+                .map(|(key, value)| (key.as_str(), value))
+                .collect(),
+        )
+    }
+}
+
+impl Tree for Ordered {
+    fn to_value(&self) -> Value {
+        Ordered::to_value(self)
+    }
+
+    fn items(&self) -> Option<&[Self]> {
+        match self {
+            Ordered::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    fn properties(&self) -> Option<Vec<(&str, &Self)>> {
+        match self {
+            Ordered::Object(props) => Some(
+                props
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// The first place where two values, produced as named, differ, if they do,
+/// preferring a difference in content over one of order.
+pub fn difference<T: Tree>(
+    pointer: String,
+    (a_name, a): (&str, &T),
+    (b_name, b): (&str, &T),
+) -> Option<(String, String)> {
+    if a == b {
+        return None;
+    }
+    let child = |segment: &str| format!("{}/{}", pointer, escape_pointer_segment(segment));
+
+    match (a.items(), b.items(), a.properties(), b.properties()) {
+        (Some(a_items), Some(b_items), _, _) if a_items.len() == b_items.len() => {
+            let same_content = a_items
+                .iter()
+                .zip(b_items)
+                .all(|(a, b)| a.to_value() == b.to_value());
+            if !same_content && sorted(a_items) == sorted(b_items) {
+                return Some((
+                    pointer,
+                    format!(
+                        "{} and {} have the same items in a different order",
+                        a_name, b_name
+                    ),
+                ));
+            }
+            a_items
+                .iter()
+                .zip(b_items)
+                .enumerate()
+                .find_map(|(i, (a, b))| difference(child(&i.to_string()), (a_name, a), (b_name, b)))
+        }
+        (Some(a_items), Some(b_items), _, _) => Some((
+            pointer,
+            format!(
+                "{} has {} items but {} has {}",
+                a_name,
+                a_items.len(),
+                b_name,
+                b_items.len()
+            ),
+        )),
+        (_, _, Some(a_props), Some(b_props)) => a_props
+            .iter()
+            .chain(&b_props)
+            .find_map(
+                |(key, _)| match (property(&a_props, key), property(&b_props, key)) {
+                    (Some(a), Some(b)) => difference(child(key), (a_name, a), (b_name, b)),
+                    (Some(_), None) => Some((child(key), format!("{} omits the property", b_name))),
+                    (None, Some(_)) => Some((child(key), format!("{} omits the property", a_name))),
+                    (None, None) => None,
+                },
+            )
+            .or_else(|| {
+                let keys = |props: &[(&str, &T)]| {
+                    props
+                        .iter()
+                        .map(|(key, _)| *key)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                Some((
+                    pointer,
+                    format!(
+                        "{} writes the properties in the order {} but {} in the order {}",
+                        a_name,
+                        keys(&a_props),
+                        b_name,
+                        keys(&b_props)
+                    ),
+                ))
+            }),
+        _ => Some((
+            pointer,
+            format!(
+                "{} produces {} but {} produces {}",
+                a_name,
+                a.to_value(),
+                b_name,
+                b.to_value()
+            ),
+        )),
+    }
+}
+
+fn property<'a, T>(props: &[(&str, &'a T)], key: &str) -> Option<&'a T> {
+    props
+        .iter()
+        .find(|(other, _)| *other == key)
+        .map(|(_, value)| *value)
+}
+
+fn sorted<T: Tree>(items: &[T]) -> Vec<String> {
+    let mut items = items
+        .iter()
+        .map(|item| item.to_value().to_string())
+        .collect::<Vec<_>>();
+    items.sort();
+    items
+}
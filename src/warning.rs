@@ -0,0 +1,57 @@
+use thiserror::Error;
+
+/// A non-fatal observation made during validation.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum Warning {
+    #[error("{path}: unknown keyword `{keyword}` was not validated")]
+    UnknownKeyword { path: String, keyword: String },
+    #[error("{path}: format `{format}` was not validated")]
+    UnvalidatedFormat { path: String, format: String },
+    #[error("{path}: unsupported contentEncoding `{encoding}` was not decoded")]
+    UnsupportedEncoding { path: String, encoding: String },
+    #[error("{path}: the property {property} is not among the declared properties")]
+    UndeclaredProperty { path: String, property: String },
+    #[error("{path}: value validated against {count} of {total} `anyOf` schemas")]
+    AmbiguousAnyOf {
+        path: String,
+        count: usize,
+        total: usize,
+    },
+    #[error("{path}: {details}")]
+    Deprecated { path: String, details: String },
+}
+
+/// The kind of a [`Warning`], used to select warnings to promote to errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WarningKind {
+    UnknownKeyword,
+    UnvalidatedFormat,
+    UnsupportedEncoding,
+    UndeclaredProperty,
+    AmbiguousAnyOf,
+    Deprecated,
+}
+
+impl Warning {
+    pub fn kind(&self) -> WarningKind {
+        match self {
+            Warning::UnknownKeyword { .. } => WarningKind::UnknownKeyword,
+            Warning::UnvalidatedFormat { .. } => WarningKind::UnvalidatedFormat,
+            Warning::UnsupportedEncoding { .. } => WarningKind::UnsupportedEncoding,
+            Warning::UndeclaredProperty { .. } => WarningKind::UndeclaredProperty,
+            Warning::AmbiguousAnyOf { .. } => WarningKind::AmbiguousAnyOf,
+            Warning::Deprecated { .. } => WarningKind::Deprecated,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        match self {
+            Warning::UnknownKeyword { path, .. }
+            | Warning::UnvalidatedFormat { path, .. }
+            | Warning::UnsupportedEncoding { path, .. }
+            | Warning::UndeclaredProperty { path, .. }
+            | Warning::AmbiguousAnyOf { path, .. }
+            | Warning::Deprecated { path, .. } => path,
+        }
+    }
+}